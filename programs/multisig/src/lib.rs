@@ -19,6 +19,9 @@
 
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use anchor_lang::solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
 use anchor_lang::solana_program::instruction::Instruction;
 use std::convert::Into;
 
@@ -41,6 +44,42 @@ security_txt! {
     auditors: "https://www.certik.com"
 }
 
+#[macro_export]
+macro_rules! audit_log_data_len {
+    () => {
+        {
+            (ANCHOR_ACCT_DESCRIM_SIZE + PUBKEY_SIZE + vec_len!(1 + PUBKEY_SIZE + PUBKEY_SIZE + 8, AUDIT_LOG_CAPACITY))
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! break_glass_data_len {
+    () => {
+        {
+            (ANCHOR_ACCT_DESCRIM_SIZE + PUBKEY_SIZE + 32 + PUBKEY_SIZE + 8 + 8)
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! stats_data_len {
+    () => {
+        {
+            (ANCHOR_ACCT_DESCRIM_SIZE + PUBKEY_SIZE + 8 + 8 + 8 + 8)
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! profile_data_len {
+    ( $name_len:expr, $owner_count:expr ) => {
+        {
+            (ANCHOR_ACCT_DESCRIM_SIZE + PUBKEY_SIZE + vec_len!(1, $name_len) + vec_len!(PUBKEY_SIZE, $owner_count) + 8)
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! vec_len {
     ( $elem_size:expr, $elem_count:expr ) => {
@@ -66,16 +105,16 @@ macro_rules! instructions_len {
 macro_rules! multisig_data_len {
     ( $owner_count:expr ) => {
         {
-            (ANCHOR_ACCT_DESCRIM_SIZE + vec_len!(PUBKEY_SIZE, $owner_count) + 8 + 1 + 4)
+            (ANCHOR_ACCT_DESCRIM_SIZE + vec_len!(PUBKEY_SIZE, $owner_count) + vec_len!(1 + 8, $owner_count) + vec_len!(8, $owner_count) + 8 + 1 + 4 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + vec_len!(PUBKEY_SIZE, MAX_EXECUTORS) + 1 + 1 + 1 + 1 + 1 + 1 + vec_len!(PUBKEY_SIZE + 32, MAX_PINNED_PROGRAMS) + vec_len!(PUBKEY_SIZE, $owner_count) + 8 + 8 + 8 + 1 + 8 + 8 + 1 + 8 + 8 + 1 + 1 + 1 + 8 + 8 + 1 + vec_len!(8, $owner_count) + vec_len!(4 + 32 + 8, OWNER_HISTORY_CAPACITY) + vec_len!(PUBKEY_SIZE, MAX_ALLOWED_PROGRAMS) + 8 + 1 + 8 + 8 + 8 + 8 + 2 + 2 + vec_len!(PUBKEY_SIZE, $owner_count) + vec_len!(1, $owner_count) + 8 + 1 + 8 + 8 + vec_len!(1, $owner_count) + 1)
         }
     };
 }
 
 #[macro_export]
 macro_rules! transaction_data_len {
-    ( $instructions:expr, $owner_count:expr ) => {
+    ( $instructions:expr, $owner_count:expr, $memo_len:expr, $display_order_len:expr, $co_multisig_count:expr ) => {
         {
-            (ANCHOR_ACCT_DESCRIM_SIZE + PUBKEY_SIZE + instructions_len!($instructions) + vec_len!(1, $owner_count) + 4)
+            (ANCHOR_ACCT_DESCRIM_SIZE + PUBKEY_SIZE + instructions_len!($instructions) + vec_len!(1, $owner_count) + 4 + 1 + 1 + 8 + 8 + 1 + PUBKEY_SIZE + PUBKEY_SIZE + 1 + vec_len!(1, $memo_len) + vec_len!(2, $display_order_len) + 1 + vec_len!(PUBKEY_SIZE, $co_multisig_count) + vec_len!(1, $co_multisig_count) + 8 + 1 + vec_len!(1, $instructions.len()) + 1 + 8 + 8 + vec_len!(1, $owner_count) + 8 + 8)
         }
     };
 }
@@ -84,63 +123,421 @@ macro_rules! transaction_data_len {
 
 declare_id!("LMAXm1DhfBg1YMvi79gXdPfsJpYuJb9urGkGNa12hvJ");
 
+// The purpose of the original multisig signer PDA, seeded by the multisig key alone.
+pub const DEFAULT_PURPOSE: u8 = 0;
+// Number of entries an audit log holds before evicting the oldest.
+pub const AUDIT_LOG_CAPACITY: usize = 32;
+// Maximum number of non-owner executors, for which space is reserved in the multisig account.
+pub const MAX_EXECUTORS: usize = 8;
+// Maximum number of programs pinned to a deployed version, for which space is reserved in the multisig account.
+pub const MAX_PINNED_PROGRAMS: usize = 4;
+// Number of owner sets the multisig remembers before evicting the oldest.
+pub const OWNER_HISTORY_CAPACITY: usize = 8;
+// Maximum number of programs transactions may be restricted to, for which space is reserved in the multisig account.
+pub const MAX_ALLOWED_PROGRAMS: usize = 8;
+// Maximum length in bytes of a transaction's memo.
+pub const MAX_MEMO_LEN: usize = 200;
+// Maximum number of owners, bounding the size of the multisig account.
+pub const MAX_OWNERS: usize = 64;
+// Bits of an owner's role, each letting them propose transactions in one category, see
+// `required_role`. Transfers are calls to the system and token programs, governance calls to this
+// program, and anything else falls under other.
+pub const ROLE_TRANSFER: u8 = 1;
+pub const ROLE_GOVERNANCE: u8 = 2;
+pub const ROLE_OTHER: u8 = 4;
+// The role of owners not given one explicitly, letting them propose anything.
+pub const ROLE_ALL: u8 = u8::MAX;
+// Largest transaction account that can be created, the most an account can grow by within an instruction.
+pub const MAX_TRANSACTION_DATA_LEN: usize = solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+// Length of the UTC days the daily limit applies to.
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+// Serialized size of an instruction with 4 accounts and 64 bytes of data, taken as typical by
+// `max_safe_instructions`.
+const AVERAGE_INSTRUCTION_LEN: usize = PUBKEY_SIZE + vec_len!(PUBKEY_SIZE + 1 + 1, 4) + vec_len!(1, 64);
+
+// The token programs, whose accounts may hold an owner's stake, see `approved_stake`.
+mod spl_token {
+    anchor_lang::declare_id!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+}
+mod spl_token_2022 {
+    anchor_lang::declare_id!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+}
+
 #[program]
 pub mod lmax_multisig {
     use super::*;
 
-    // Initializes a new multisig account with a set of owners and a threshold.
+    // Initializes a new multisig account with a set of owners and a threshold. Transactions
+    // may not be executed until `min_delay` seconds after creation, a timelock which, unlike the
+    // quarantine of set_quarantine_secs, is fixed for the life of the multisig.
+    // `weights`, if given, are how much each owner's approval counts towards the threshold,
+    // otherwise every approval counts once.
     pub fn create_multisig(
         ctx: Context<CreateMultisig>,
         owners: Vec<Pubkey>,
         threshold: u64,
         nonce: u8,
+        min_delay: i64,
+        weights: Option<Vec<u64>>,
+    ) -> Result<()> {
+        init_multisig(&mut ctx.accounts.multisig, ctx.accounts.multisig_signer.key, owners, threshold, nonce, min_delay, weights)
+    }
+
+    // Initializes a new multisig account at the address derived from the payer and `seed`, rather
+    // than at a fresh keypair, so clients can predict it. Deriving it from the payer too keeps
+    // anyone else from taking the address first. The multisig signer is derived from the
+    // multisig address as for any other multisig, and the other arguments are as for
+    // create_multisig.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_multisig_with_seed(
+        ctx: Context<CreateMultisigWithSeed>,
+        owners: Vec<Pubkey>,
+        threshold: u64,
+        nonce: u8,
+        min_delay: i64,
+        weights: Option<Vec<u64>>,
+        _seed: [u8; 32],
+    ) -> Result<()> {
+        init_multisig(&mut ctx.accounts.multisig, ctx.accounts.multisig_signer.key, owners, threshold, nonce, min_delay, weights)
+    }
+
+    // Initializes a new multisig account with the owners, threshold and configuration exported
+    // from another with export_config, e.g. to migrate it to a new account.
+    pub fn create_multisig_from_config(
+        ctx: Context<CreateMultisigFromConfig>,
+        config: MultisigConfig,
+        nonce: u8,
     ) -> Result<()> {
-        assert_unique_owners(&owners)?;
+        let multisig = &mut ctx.accounts.multisig;
+        init_multisig(
+            multisig,
+            ctx.accounts.multisig_signer.key,
+            config.owners,
+            config.threshold,
+            nonce,
+            config.min_delay,
+            Some(config.weights),
+        )?;
+        require!(config.owner_expiries.len() == multisig.owners.len(), ErrorCode::InvalidConfig);
+        require!(config.owner_roles.len() == multisig.owners.len(), ErrorCode::InvalidConfig);
+        require!(config.executors.len() <= MAX_EXECUTORS, ErrorCode::TooManyExecutors);
+        require!(config.pinned_programs.len() <= MAX_PINNED_PROGRAMS, ErrorCode::TooManyPinnedPrograms);
+        require!(config.allowed_programs.len() <= MAX_ALLOWED_PROGRAMS, ErrorCode::TooManyAllowedPrograms);
         require!(
-            threshold > 0 && threshold <= owners.len() as u64,
-            ErrorCode::InvalidThreshold
+            config.stake_accounts.is_empty() || (config.stake_accounts.len() == multisig.owners.len() && config.stake_threshold > 0),
+            ErrorCode::InvalidStakeWeighting
         );
-        require!(!owners.is_empty(), ErrorCode::NotEnoughOwners);
+        require!(config.max_pending_secs > 0, ErrorCode::InvalidMaxPendingSecs);
+        require!(config.default_expiry_secs > 0, ErrorCode::InvalidDefaultExpirySecs);
 
-        let multisig = &mut ctx.accounts.multisig;
-        multisig.owners = owners;
-        multisig.threshold = threshold;
-        multisig.nonce = nonce;
-        multisig.owner_set_seqno = 0;
-        Ok(())
+        multisig.owner_expiries = config.owner_expiries;
+        multisig.max_sol_outflow = config.max_sol_outflow;
+        multisig.max_pending_secs = config.max_pending_secs;
+        multisig.default_expiry_secs = config.default_expiry_secs;
+        multisig.owner_change_cooldown = config.owner_change_cooldown;
+        multisig.new_owner_delay = config.new_owner_delay;
+        multisig.churn_grace_secs = config.churn_grace_secs;
+        multisig.executors = config.executors;
+        multisig.require_distinct_payer_proposer = config.require_distinct_payer_proposer;
+        multisig.require_signer_rent_exemption = config.require_signer_rent_exemption;
+        multisig.threshold_against_active = config.threshold_against_active;
+        multisig.snapshot_quorum = config.snapshot_quorum;
+        multisig.require_memo = config.require_memo;
+        multisig.reject_duplicate_approvals = config.reject_duplicate_approvals;
+        multisig.pinned_programs = config.pinned_programs;
+        multisig.stake_accounts = config.stake_accounts;
+        multisig.stake_threshold = config.stake_threshold;
+        multisig.admin_stake_threshold = config.admin_stake_threshold;
+        multisig.weakening_delay = config.weakening_delay;
+        multisig.max_open_owner_changes = config.max_open_owner_changes;
+        multisig.public_execution = config.public_execution;
+        multisig.quarantine_secs = config.quarantine_secs;
+        multisig.strict_separation = config.strict_separation;
+        multisig.allowed_programs = config.allowed_programs;
+        multisig.read_threshold = config.read_threshold;
+        multisig.cancel_approval_limit = config.cancel_approval_limit;
+        assert_valid_admin_threshold(config.admin_threshold, multisig.threshold)?;
+        multisig.admin_threshold = config.admin_threshold;
+        assert_valid_admin_stake_threshold(multisig)?;
+        multisig.daily_limit = config.daily_limit;
+        multisig.max_instructions = config.max_instructions;
+        multisig.max_data_len = config.max_data_len;
+        assert_valid_cancel_threshold(config.cancel_threshold, multisig.owners.len())?;
+        multisig.cancel_threshold = config.cancel_threshold;
+        multisig.owner_roles = config.owner_roles;
+        multisig.no_single_owner_majority = config.no_single_owner_majority;
+        assert_no_single_owner_majority(multisig)
+    }
+
+    // Returns the owners, threshold and configuration of the multisig, for
+    // create_multisig_from_config. Runtime state, such as pending threshold changes and the owner
+    // set sequence number, isn't carried over. Return data is limited to 1024 bytes, which bounds
+    // how many owners and executors can be exported.
+    pub fn export_config(ctx: Context<ViewMultisig>) -> Result<MultisigConfig> {
+        let multisig = &ctx.accounts.multisig;
+        Ok(MultisigConfig {
+            owners: multisig.owners.clone(),
+            threshold: multisig.threshold,
+            owner_expiries: multisig.owner_expiries.clone(),
+            max_sol_outflow: multisig.max_sol_outflow,
+            max_pending_secs: multisig.max_pending_secs,
+            default_expiry_secs: multisig.default_expiry_secs,
+            owner_change_cooldown: multisig.owner_change_cooldown,
+            new_owner_delay: multisig.new_owner_delay,
+            churn_grace_secs: multisig.churn_grace_secs,
+            executors: multisig.executors.clone(),
+            require_distinct_payer_proposer: multisig.require_distinct_payer_proposer,
+            require_signer_rent_exemption: multisig.require_signer_rent_exemption,
+            threshold_against_active: multisig.threshold_against_active,
+            snapshot_quorum: multisig.snapshot_quorum,
+            require_memo: multisig.require_memo,
+            reject_duplicate_approvals: multisig.reject_duplicate_approvals,
+            pinned_programs: multisig.pinned_programs.clone(),
+            stake_accounts: multisig.stake_accounts.clone(),
+            stake_threshold: multisig.stake_threshold,
+            admin_stake_threshold: multisig.admin_stake_threshold,
+            weakening_delay: multisig.weakening_delay,
+            max_open_owner_changes: multisig.max_open_owner_changes,
+            public_execution: multisig.public_execution,
+            quarantine_secs: multisig.quarantine_secs,
+            min_delay: multisig.min_delay,
+            strict_separation: multisig.strict_separation,
+            weights: multisig.weights.clone(),
+            allowed_programs: multisig.allowed_programs.clone(),
+            read_threshold: multisig.read_threshold,
+            cancel_approval_limit: multisig.cancel_approval_limit,
+            admin_threshold: multisig.admin_threshold,
+            daily_limit: multisig.daily_limit,
+            max_instructions: multisig.max_instructions,
+            max_data_len: multisig.max_data_len,
+            cancel_threshold: multisig.cancel_threshold,
+            owner_roles: multisig.owner_roles.clone(),
+            no_single_owner_majority: multisig.no_single_owner_majority,
+        })
+    }
+
+    // Checks the given instructions against every policy of the multisig create_transaction
+    // would check them against, returning the first violated, without creating any account.
+    // Policies on the proposer, payer, memo and expiry are still only checked on creation.
+    pub fn validate_proposal(ctx: Context<ViewMultisig>, instructions: Vec<TransactionInstruction>) -> Result<()> {
+        assert_valid_instructions(&ctx.accounts.multisig, &instructions)
     }
 
     // Creates a new transaction account, automatically signed by the creator,
-    // which must be one of the owners of the multisig.
+    // which must be one of the owners of the multisig. The purpose selects which
+    // of the multisig's signer PDAs the instructions are executed with. The
+    // transaction can no longer be executed from `expires_at` (a unix timestamp),
+    // which may be at most the multisig's `max_pending_secs` after creation. When
+    // omitted (or 0) it defaults to `default_expiry_secs` after creation, within
+    // that maximum. With `require_consistent_signers`, every instruction
+    // must expect the same external signers, so a bundle can't partially fail for
+    // want of a signer only some instructions need. A `designated_executor`, if
+    // given, is the only key allowed to execute the transaction. The memo is a
+    // human-readable note, of at most MAX_MEMO_LEN bytes, on what the transaction is for.
+    // A non-empty `display_order` is the order, by index, clients should show the
+    // instructions in; they are still executed in the order given. Every one of the `co_multisigs` must also approve the
+    // transaction, with co_approve, before it can be executed. The payer funds a `keeper_reward`
+    // in lamports, held by the transaction account and paid to whoever executes it. Unless
+    // `proposer_approves`, the transaction starts without the proposer's approval. With
+    // `allow_out_of_order`, execute_transaction_by_index may execute the instructions in any order.
+    #[allow(clippy::too_many_arguments)]
     pub fn create_transaction(
         ctx: Context<CreateTransaction>,
         instructions: Vec<TransactionInstruction>,
+        purpose: u8,
+        expires_at: Option<i64>,
+        require_consistent_signers: bool,
+        designated_executor: Option<Pubkey>,
+        memo: String,
+        display_order: Vec<u16>,
+        co_multisigs: Vec<Pubkey>,
+        keeper_reward: u64,
+        proposer_approves: bool,
+        allow_out_of_order: bool,
     ) -> Result<()> {
-        require!(!instructions.is_empty(), ErrorCode::MissingInstructions);
+        assert_display_order(&display_order, instructions.len())?;
+        if require_consistent_signers {
+            let signer = transaction_signer_address(&ctx.accounts.multisig, purpose)?;
+            assert_consistent_signers(&instructions, &signer)?;
+        }
+        init_transaction(
+            &mut ctx.accounts.transaction,
+            &mut ctx.accounts.multisig,
+            ctx.accounts.proposer.key,
+            ctx.accounts.payer.key,
+            instructions,
+            purpose,
+            expires_at,
+            memo,
+        )?;
+        ctx.accounts.transaction.designated_executor = designated_executor;
+        ctx.accounts.transaction.display_order = display_order;
+        ctx.accounts.transaction.co_approvals = vec![false; co_multisigs.len()];
+        ctx.accounts.transaction.co_multisigs = co_multisigs;
+        ctx.accounts.transaction.keeper_reward = keeper_reward;
+        ctx.accounts.transaction.out_of_order = allow_out_of_order;
+        if !proposer_approves {
+            // The proposer's is the only approval so far.
+            let transaction = &mut ctx.accounts.transaction;
+            transaction.signers.iter_mut().for_each(|signed| *signed = false);
+            transaction.reached_threshold = has_reached_threshold(&ctx.accounts.multisig, transaction, transaction.created_at, &[]);
+        }
+        if keeper_reward > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: ctx.accounts.transaction.to_account_info(),
+                    },
+                ),
+                keeper_reward,
+            )?;
+        }
+        record_transaction_created(
+            &ctx.accounts.multisig.key(),
+            &ctx.accounts.transaction,
+            ctx.accounts.proposer.key,
+            &mut ctx.accounts.stats,
+            &mut ctx.accounts.audit_log,
+        )
+    }
+
+    // Proposes handing the upgrade authority of a BPF upgradeable program, currently held by the
+    // multisig signer, over to a new authority. The transaction is created and approved like any
+    // other; on execution the current authority is verified before the loader is invoked.
+    pub fn set_program_upgrade_authority(
+        ctx: Context<SetProgramUpgradeAuthority>,
+        new_authority: Pubkey,
+        memo: String,
+    ) -> Result<()> {
+        let instruction = set_upgrade_authority_instruction(
+            ctx.accounts.program.key,
+            ctx.accounts.multisig_signer.key,
+            &new_authority,
+        );
+        init_transaction(
+            &mut ctx.accounts.transaction,
+            &mut ctx.accounts.multisig,
+            ctx.accounts.proposer.key,
+            ctx.accounts.payer.key,
+            vec![instruction],
+            DEFAULT_PURPOSE,
+            None,
+            memo,
+        )?;
+        record_transaction_created(
+            &ctx.accounts.multisig.key(),
+            &ctx.accounts.transaction,
+            ctx.accounts.proposer.key,
+            &mut ctx.accounts.stats,
+            &mut ctx.accounts.audit_log,
+        )
+    }
+
+    // Proposes transferring `amount` tokens out of a token account held by the multisig signer, as
+    // an SPL token transfer built here rather than by the client. The transaction is created and
+    // approved like any other.
+    pub fn propose_token_transfer(ctx: Context<ProposeTokenTransfer>, amount: u64, memo: String) -> Result<()> {
+        // Token account state: mint, then the owner.
+        let source = &ctx.accounts.source;
+        let data = source.try_borrow_data()?;
+        require!(
+            [spl_token::ID, spl_token_2022::ID].contains(ctx.accounts.token_program.key)
+                && source.owner == ctx.accounts.token_program.key
+                && data.len() >= 64
+                && data[32..64] == ctx.accounts.multisig_signer.key.to_bytes(),
+            ErrorCode::InvalidTokenSource
+        );
+        drop(data);
+
+        let instruction = token_transfer_instruction(
+            ctx.accounts.token_program.key,
+            source.key,
+            ctx.accounts.destination.key,
+            ctx.accounts.multisig_signer.key,
+            amount,
+        );
+        init_transaction(
+            &mut ctx.accounts.transaction,
+            &mut ctx.accounts.multisig,
+            ctx.accounts.proposer.key,
+            ctx.accounts.payer.key,
+            vec![instruction],
+            DEFAULT_PURPOSE,
+            None,
+            memo,
+        )?;
+        record_transaction_created(
+            &ctx.accounts.multisig.key(),
+            &ctx.accounts.transaction,
+            ctx.accounts.proposer.key,
+            &mut ctx.accounts.stats,
+            &mut ctx.accounts.audit_log,
+        )
+    }
+
+    // Approves a transaction on behalf of an owner of the multisig. The stake accounts of a stake
+    // weighted multisig should be passed in to record whether it has reached its threshold, for
+    // churn protection.
+    pub fn approve(ctx: Context<Approve>) -> Result<()> {
+        approve_transaction(ctx, false)
+    }
 
+    // Approves a transaction on behalf of an owner of the multisig and, should that bring it to
+    // the threshold, executes it as execute_transaction would, whether or not it is auto-executing.
+    // The stake accounts of a stake weighted multisig must be passed in along with the accounts the
+    // instructions reference.
+    pub fn approve_and_execute(ctx: Context<Approve>) -> Result<()> {
+        approve_transaction(ctx, true)
+    }
+
+    // Approves every transaction of the multisig passed in the remaining accounts at once. Those
+    // the owner has already approved are skipped. Auto-executing transactions are only approved,
+    // as approving with approve executes them.
+    pub fn approve_many<'info>(ctx: Context<'_, '_, 'info, 'info, ApproveMany<'info>>) -> Result<()> {
         let owner_index = ctx
             .accounts
             .multisig
             .owners
             .iter()
-            .position(|a| a == ctx.accounts.proposer.key)
+            .position(|a| a == ctx.accounts.owner.key)
             .ok_or(ErrorCode::InvalidOwner)?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(is_owner_active(&ctx.accounts.multisig, owner_index, now), ErrorCode::OwnerExpired);
+        require!(!is_owner_cooling_off(&ctx.accounts.multisig, owner_index, now), ErrorCode::NewOwnerCoolingOff);
 
-        let mut signers = Vec::new();
-        signers.resize(ctx.accounts.multisig.owners.len(), false);
-        signers[owner_index] = true;
-
-        let tx = &mut ctx.accounts.transaction;
-        tx.instructions = instructions;
-        tx.signers = signers;
-        tx.multisig = ctx.accounts.multisig.key();
-        tx.owner_set_seqno = ctx.accounts.multisig.owner_set_seqno;
+        for info in ctx.remaining_accounts.iter() {
+            let mut transaction = Account::<Transaction>::try_from(info)?;
+            require_keys_eq!(transaction.multisig, ctx.accounts.multisig.key(), ErrorCode::InvalidTransaction);
+            require!(transaction.owner_set_seqno == ctx.accounts.multisig.owner_set_seqno, ErrorCode::InvalidTransaction);
+            assert_not_frozen(&ctx.accounts.multisig, &transaction.instructions)?;
+            require!(!transaction.did_execute, ErrorCode::AlreadyExecuted);
+            if transaction.signers[owner_index] {
+                continue;
+            }
+            transaction.signers[owner_index] = true;
+            transaction.reached_threshold = has_reached_threshold(&ctx.accounts.multisig, &transaction, now, &[]);
+            transaction.exit(ctx.program_id)?;
 
+            record_audit_entry(
+                &mut ctx.accounts.audit_log,
+                AuditAction::Approve,
+                ctx.accounts.owner.key,
+                &transaction.key(),
+            )?;
+            emit!(TransactionApproved {
+                multisig: ctx.accounts.multisig.key(),
+                transaction: transaction.key(),
+                owner: ctx.accounts.owner.key(),
+                owner_set_seqno: transaction.owner_set_seqno,
+            });
+        }
         Ok(())
     }
 
-    // Approves a transaction on behalf of an owner of the multisig.
-    pub fn approve(ctx: Context<Approve>) -> Result<()> {
+    // Withdraws an owner's approval of a transaction that has not yet been executed.
+    pub fn revoke(ctx: Context<Approve>) -> Result<()> {
         let owner_index = ctx
             .accounts
             .multisig
@@ -148,30 +545,278 @@ pub mod lmax_multisig {
             .iter()
             .position(|a| a == ctx.accounts.owner.key)
             .ok_or(ErrorCode::InvalidOwner)?;
+        let now = Clock::get()?.unix_timestamp;
+        assert_not_frozen(&ctx.accounts.multisig, &ctx.accounts.transaction.instructions)?;
+        require!(!ctx.accounts.transaction.did_execute, ErrorCode::AlreadyExecuted);
+        ctx.accounts.transaction.signers[owner_index] = false;
+        ctx.accounts.transaction.reached_threshold = has_reached_threshold(&ctx.accounts.multisig, &ctx.accounts.transaction, now, ctx.remaining_accounts);
 
-        ctx.accounts.transaction.signers[owner_index] = true;
+        record_audit_entry(
+            &mut ctx.accounts.audit_log,
+            AuditAction::Revoke,
+            ctx.accounts.owner.key,
+            &ctx.accounts.transaction.key(),
+        )?;
+        emit!(TransactionRevoked {
+            multisig: ctx.accounts.multisig.key(),
+            transaction: ctx.accounts.transaction.key(),
+            owner: ctx.accounts.owner.key(),
+            owner_set_seqno: ctx.accounts.transaction.owner_set_seqno,
+        });
+        Ok(())
+    }
 
+    // Approves a transaction of another multisig on behalf of `co_multisig`, which the transaction
+    // lists as a co-multisig. The only way this can be invoked is via a recursive call from
+    // execute_transaction of the co-multisig, so needs it to reach its own threshold.
+    pub fn co_approve(ctx: Context<CoApprove>) -> Result<()> {
+        let transaction = &mut ctx.accounts.transaction;
+        let co_multisig_index = transaction.co_multisigs.iter()
+            .position(|co_multisig| *co_multisig == ctx.accounts.co_multisig.key())
+            .ok_or(ErrorCode::NotACoMultisig)?;
+        transaction.co_approvals[co_multisig_index] = true;
         Ok(())
     }
 
-    // Set owners and threshold at once.
+    // Returns each owner paired with whether they have approved the transaction.
+    pub fn approval_roster(ctx: Context<ViewTransaction>) -> Result<Vec<OwnerApproval>> {
+        Ok(ctx.accounts.multisig.owners.iter()
+            .zip(ctx.accounts.transaction.signers.iter())
+            .map(|(owner, approved)| OwnerApproval { owner: *owner, approved: *approved })
+            .collect())
+    }
+
+    // Returns a receipt of whether `owner` has approved the transaction. Programs calling this get
+    // it back as return data attributed to this program, so can rely on it as an attestation.
+    pub fn approval_receipt(ctx: Context<ViewTransaction>, owner: Pubkey) -> Result<ApprovalReceipt> {
+        let owner_index = ctx.accounts.multisig.owners.iter()
+            .position(|a| *a == owner)
+            .ok_or(ErrorCode::InvalidOwner)?;
+        Ok(ApprovalReceipt {
+            multisig: ctx.accounts.multisig.key(),
+            transaction: ctx.accounts.transaction.key(),
+            owner,
+            owner_set_seqno: ctx.accounts.transaction.owner_set_seqno,
+            approved: ctx.accounts.transaction.signers[owner_index],
+        })
+    }
+
+    // Returns whether the given key is eligible to execute the transaction.
+    pub fn can_execute(ctx: Context<ViewTransaction>, who: Pubkey) -> Result<bool> {
+        Ok(is_eligible_executor(&ctx.accounts.multisig, &ctx.accounts.transaction, &who))
+    }
+
+    // Returns whether executing the transaction would currently pass its approval, owner set and
+    // timing checks, leaving aside who executes it. Stake-weighted multisigs need the stake
+    // accounts passed as remaining accounts, as for execute_transaction.
+    pub fn check_executable(ctx: Context<CheckExecutable>) -> Result<bool> {
+        let multisig = &ctx.accounts.multisig;
+        let transaction = &ctx.accounts.transaction;
+        let now = Clock::get()?.unix_timestamp;
+        Ok(!transaction.did_execute
+            && (!multisig.frozen || is_unfreeze(&transaction.instructions))
+            && now < transaction.expires_at
+            && is_owner_set_current_or_grandfathered(multisig, transaction, now)
+            && now >= executable_at(multisig, transaction)
+            && transaction.co_approvals.iter().all(|approved| *approved)
+            && has_quorum(multisig, transaction, now, ctx.remaining_accounts)?)
+    }
+
+    // Returns the number of approvals the transaction currently needs to be executed, after
+    // every modifier configured on the multisig has been applied.
+    pub fn effective_threshold(ctx: Context<ViewTransaction>) -> Result<u64> {
+        match ctx.accounts.multisig.stake_accounts.is_empty() {
+            true => Ok(required_approvals(&ctx.accounts.multisig, &ctx.accounts.transaction, Clock::get()?.unix_timestamp)),
+            false => Ok(required_stake(&ctx.accounts.multisig, &ctx.accounts.transaction)),
+        }
+    }
+
+    // Returns the number of seconds until the time gates on the transaction allow it to be
+    // executed, zero or less meaning it may be executed now (given enough approvals).
+    pub fn time_until_executable(ctx: Context<ViewTransaction>) -> Result<i64> {
+        Ok(executable_at(&ctx.accounts.multisig, &ctx.accounts.transaction).saturating_sub(Clock::get()?.unix_timestamp))
+    }
+
+    // Returns the unix timestamp from which the transaction can no longer be executed, as enforced
+    // after defaulting and capping at creation rather than as requested by the proposer.
+    pub fn effective_expiry(ctx: Context<ViewTransaction>) -> Result<i64> {
+        Ok(ctx.accounts.transaction.expires_at)
+    }
+
+    // Set owners and threshold at once. The pair is validated together before either is changed.
     pub fn set_owners_and_change_threshold<'info>(
         ctx: Context<'_, '_, '_, 'info, Auth<'info>>,
         owners: Vec<Pubkey>,
         threshold: u64,
     ) -> Result<()> {
         let multisig = &mut ctx.accounts.multisig;
-        execute_set_owners(multisig, owners)?;
-        execute_change_threshold(multisig, threshold)
+        assert_valid_threshold(threshold, total_weight(&remapped_weights(multisig, &owners)))?;
+        execute_set_owners(multisig, owners, Some(threshold))
     }
 
     // Sets the owners field on the multisig. The only way this can be invoked
     // is via a recursive call from execute_transaction -> set_owners.
     pub fn set_owners(ctx: Context<Auth>, owners: Vec<Pubkey>) -> Result<()> {
-        execute_set_owners(&mut ctx.accounts.multisig, owners)
+        execute_set_owners(&mut ctx.accounts.multisig, owners, None)
+    }
+
+    // Removes a single owner, lowering the threshold if it would exceed the remaining owners. The
+    // only way this can be invoked is via a recursive call from execute_transaction.
+    pub fn remove_owner(ctx: Context<Auth>, owner: Pubkey) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        require!(multisig.owners.contains(&owner), ErrorCode::InvalidOwner);
+        let owners = multisig.owners.iter().filter(|a| **a != owner).copied().collect();
+        execute_set_owners(multisig, owners, None)
+    }
+
+    // Closes the multisig account, refunding its rent to the refundee, when decommissioning it.
+    // Any funds held by the multisig's signer PDAs must be moved out first, e.g. by earlier
+    // instructions of the same transaction, as nothing can sign for them afterwards. Transactions
+    // of the multisig can't be checked for here, so should be cancelled beforehand to reclaim
+    // their rent. The only way this can be invoked is via a recursive call from
+    // execute_transaction.
+    pub fn close_multisig(_ctx: Context<CloseMultisig>) -> Result<()> {
+        Ok(())
+    }
+
+    // Appends new owners, growing the multisig account to fit them at the payer's expense. The
+    // threshold is unchanged. The only way this can be invoked is via a recursive call from
+    // execute_transaction.
+    pub fn add_owners(ctx: Context<AddOwners>, new_owners: Vec<Pubkey>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let owners = [multisig.owners.as_slice(), new_owners.as_slice()].concat();
+        execute_set_owners(multisig, owners, None)
+    }
+
+    // Rotates a single owner's key, the new key taking the old one's place in the list along with
+    // its weight, expiry and anything else recorded for it, other than when it was added: the new
+    // key waits out the new owner delay like any other. The only way this can be invoked is via a
+    // recursive call from execute_transaction.
+    pub fn replace_owner(ctx: Context<Auth>, old_owner: Pubkey, new_owner: Pubkey) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let owner_index = multisig.owners.iter()
+            .position(|a| *a == old_owner)
+            .ok_or(ErrorCode::InvalidOwner)?;
+        require!(!multisig.owners.contains(&new_owner), ErrorCode::UniqueOwners);
+        let mut owners = multisig.owners.clone();
+        owners[owner_index] = new_owner;
+        let carried_from = (0..owners.len()).map(Some).collect();
+        execute_set_owners_carrying(multisig, owners, carried_from, None)?;
+        multisig.owner_added_at[owner_index] = multisig.last_owner_change_at;
+        Ok(())
+    }
+
+    // Sets the role of an owner, the bits of which are the categories of transaction they may
+    // propose. The only way this can be invoked is via a recursive call from execute_transaction.
+    pub fn set_owner_role(ctx: Context<Auth>, owner: Pubkey, role: u8) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let owner_index = multisig.owners.iter()
+            .position(|a| *a == owner)
+            .ok_or(ErrorCode::InvalidOwner)?;
+        multisig.owner_roles[owner_index] = role;
+        Ok(())
+    }
+
+    // Applies every setting given in `config` to an owner at once, each as its own setter would.
+    // The only way this can be invoked is via a recursive call from execute_transaction.
+    pub fn configure_owner(ctx: Context<Auth>, owner: Pubkey, config: OwnerConfig) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let owner_index = multisig.owners.iter()
+            .position(|a| *a == owner)
+            .ok_or(ErrorCode::InvalidOwner)?;
+        if let Some(weight) = config.weight {
+            let mut weights = multisig.weights.clone();
+            weights[owner_index] = weight;
+            assert_valid_weights(&weights, multisig.owners.len())?;
+            assert_valid_threshold(multisig.threshold, total_weight(&weights))?;
+            multisig.weights = weights;
+        }
+        if let Some(role) = config.role {
+            multisig.owner_roles[owner_index] = role;
+        }
+        if config.remove_expiry {
+            multisig.owner_expiries[owner_index] = None;
+        } else if config.expires_at.is_some() {
+            multisig.owner_expiries[owner_index] = config.expires_at;
+        }
+        assert_no_single_owner_majority(multisig)
+    }
+
+    // Proposes replacing the owners with `owners`, which happens only once every one of them has
+    // confirmed control of their key with accept_ownership, before `expires_at`. Replaces any
+    // owners proposed before. The only way this can be invoked is via a recursive call from
+    // execute_transaction.
+    pub fn propose_owners(ctx: Context<Auth>, owners: Vec<Pubkey>, expires_at: i64) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        validate_owners(&owners, &transaction_signer_address(multisig, DEFAULT_PURPOSE)?)?;
+        require!(!owners.is_empty(), ErrorCode::NotEnoughOwners);
+        require!(multisig_data_len!(owners.len()) <= multisig.to_account_info().data_len(), ErrorCode::TooManyOwners);
+        let now = Clock::get()?.unix_timestamp;
+        require!(expires_at > now, ErrorCode::PendingOwnersExpired);
+        require!(expires_at <= now.saturating_add(multisig.max_pending_secs), ErrorCode::ExpiryTooLong);
+        multisig.pending_owner_accepted = vec![false; owners.len()];
+        multisig.pending_owners = owners;
+        multisig.pending_owners_expire_at = expires_at;
+        // The proposal proposing them is no longer open, though the owners are yet to change.
+        multisig.open_owner_changes = multisig.open_owner_changes.saturating_sub(1);
+        Ok(())
+    }
+
+    // Confirms that a proposed owner controls their key. The last to do so swaps the proposed
+    // owners in.
+    pub fn accept_ownership(ctx: Context<AcceptOwnership>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let pending_index = multisig.pending_owners.iter()
+            .position(|a| a == ctx.accounts.pending_owner.key)
+            .ok_or(ErrorCode::NotAPendingOwner)?;
+        require!(
+            Clock::get()?.unix_timestamp < multisig.pending_owners_expire_at,
+            ErrorCode::PendingOwnersExpired
+        );
+        multisig.pending_owner_accepted[pending_index] = true;
+        if !multisig.pending_owner_accepted.iter().all(|accepted| *accepted) {
+            return Ok(());
+        }
+        let owners = multisig.pending_owners.clone();
+        execute_set_owners(multisig, owners, None)
+    }
+
+    // Replaces the nonce of a multisig created with one other than the canonical bump of its
+    // signer, which can't otherwise execute transactions. The multisig signer address changes to
+    // the canonical one, so anything held by the old one should be moved over by earlier
+    // instructions of the same transaction. The only way this can be invoked is via a recursive
+    // call from execute_transaction.
+    pub fn reset_nonce(ctx: Context<Auth>, new_nonce: u8) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        require!(
+            Pubkey::find_program_address(&[multisig.key().as_ref()], ctx.program_id).1 == new_nonce,
+            ErrorCode::InvalidNonce
+        );
+        multisig.nonce = new_nonce;
+        Ok(())
+    }
+
+    // Halts proposing, approving and executing the multisig's transactions, other than one
+    // unfreezing it, e.g. on discovering a compromised key. Any single owner may do so.
+    pub fn freeze(ctx: Context<OwnerAction>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let owner_index = multisig.owners.iter()
+            .position(|a| a == ctx.accounts.owner.key)
+            .ok_or(ErrorCode::InvalidOwner)?;
+        require!(is_owner_active(multisig, owner_index, Clock::get()?.unix_timestamp), ErrorCode::OwnerExpired);
+        multisig.frozen = true;
+        Ok(())
+    }
+
+    // Resumes execution of the multisig's transactions after a freeze. The only way this can be
+    // invoked is via a recursive call from execute_transaction, the one execution a freeze allows.
+    pub fn unfreeze(ctx: Context<Auth>) -> Result<()> {
+        ctx.accounts.multisig.frozen = false;
+        Ok(())
     }
 
-    // Changes the execution threshold of the multisig. The only way this can be
+    // Changes the execution threshold of the multisig, or with a weakening delay set, only
+    // schedules a lower threshold for finalize_threshold_change. The only way this can be
     // invoked is via a recursive call from execute_transaction ->
     // change_threshold.
     pub fn change_threshold(ctx: Context<Auth>, threshold: u64) -> Result<()> {
@@ -179,228 +824,2938 @@ pub mod lmax_multisig {
         execute_change_threshold(multisig, threshold)
     }
 
-    // Executes the given transaction if threshold owners have signed it.
-    pub fn execute_transaction(ctx: Context<ExecuteTransaction>) -> Result<()> {
-        require!(ctx.accounts.multisig.owners.contains(ctx.accounts.executor.key), ErrorCode::InvalidExecutor);
+    // Applies a lowered threshold scheduled by change_threshold once the weakening delay has
+    // passed. Any owner may do so, the change having already been approved.
+    pub fn finalize_threshold_change(ctx: Context<FinalizeThresholdChange>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        require!(multisig.owners.contains(ctx.accounts.owner.key), ErrorCode::InvalidOwner);
+        let threshold = multisig.pending_threshold.ok_or(ErrorCode::NoPendingThresholdChange)?;
+        require!(
+            Clock::get()?.unix_timestamp >= multisig.pending_threshold_ready_at,
+            ErrorCode::WeakeningDelayNotElapsed
+        );
+        assert_valid_threshold(threshold, total_weight(&multisig.weights))?;
+        multisig.threshold = threshold;
+        multisig.pending_threshold = None;
+        assert_no_single_owner_majority(multisig)
+    }
 
-        // Do we have enough signers?
-        let sig_count = ctx.accounts.transaction.signers.iter()
-            .filter(|&did_sign| *did_sign)
-            .count() as u64;
-        require!(sig_count >= ctx.accounts.multisig.threshold, ErrorCode::NotEnoughSigners);
+    // Sets the seconds a lowered threshold must wait before it can be finalized, 0 to apply it
+    // straight away. Shortening the delay is itself a weakening, so waits out the current delay
+    // before finalize_weakening_delay_change can apply it. The only way this can be invoked is via
+    // a recursive call from execute_transaction.
+    pub fn set_weakening_delay(ctx: Context<Auth>, weakening_delay: i64) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if weakening_delay < multisig.weakening_delay {
+            multisig.pending_weakening_delay = Some(weakening_delay);
+            multisig.pending_weakening_delay_ready_at = Clock::get()?.unix_timestamp.saturating_add(multisig.weakening_delay);
+            return Ok(());
+        }
+        multisig.weakening_delay = weakening_delay;
+        multisig.pending_weakening_delay = None;
+        Ok(())
+    }
 
-        let multisig_key = ctx.accounts.multisig.key();
-        let seeds = &[multisig_key.as_ref(), &[ctx.accounts.multisig.nonce]];
-        let signer = &[&seeds[..]];
-        let accounts = ctx.remaining_accounts;
-
-        // Execute the transaction signed by the multisig.
-        ctx.accounts.transaction.instructions.iter()
-            .map(|ix| {
-                let mut ix: Instruction = ix.into();
-                ix.accounts = ix.accounts.iter()
-                    .map(|acc| {
-                        let mut acc = acc.clone();
-                        if &acc.pubkey == ctx.accounts.multisig_signer.key {
-                            acc.is_signer = true;
-                        }
-                        acc
-                    })
-                    .collect();
-                solana_program::program::invoke_signed(&ix, accounts, signer)
-            })
-            // Collect will process Result objects from the invoke_signed until it finds an error, when it will return that error
-            .collect::<std::result::Result<Vec<_>, _>>()?;
+    // Applies a shortened weakening delay scheduled by set_weakening_delay once the current delay
+    // has passed. Any owner may do so, the change having already been approved.
+    pub fn finalize_weakening_delay_change(ctx: Context<FinalizeWeakeningDelayChange>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        require!(multisig.owners.contains(ctx.accounts.owner.key), ErrorCode::InvalidOwner);
+        let weakening_delay = multisig.pending_weakening_delay.ok_or(ErrorCode::NoPendingWeakeningDelayChange)?;
+        require!(
+            Clock::get()?.unix_timestamp >= multisig.pending_weakening_delay_ready_at,
+            ErrorCode::WeakeningDelayNotElapsed
+        );
+        multisig.weakening_delay = weakening_delay;
+        multisig.pending_weakening_delay = None;
+        Ok(())
+    }
 
+    // Saves the current owners and threshold as the profile `name`, for restore_profile to
+    // return to later. The only way this can be invoked is via a recursive call from
+    // execute_transaction.
+    pub fn save_profile(ctx: Context<SaveProfile>, name: String) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+        profile.multisig = ctx.accounts.multisig.key();
+        profile.name = name;
+        profile.owners = ctx.accounts.multisig.owners.clone();
+        profile.threshold = ctx.accounts.multisig.threshold;
         Ok(())
     }
 
-    // Cancel the given transaction regardless of signatures.
-    pub fn cancel_transaction(ctx: Context<CancelTransaction>) -> Result<()> {
-        require!(ctx.accounts.multisig.owners.contains(ctx.accounts.executor.key), ErrorCode::InvalidExecutor);
+    // Sets the owners and threshold to those saved in a profile, as set_owners_and_change_threshold
+    // would. The only way this can be invoked is via a recursive call from execute_transaction.
+    pub fn restore_profile(ctx: Context<RestoreProfile>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        assert_valid_threshold(
+            ctx.accounts.profile.threshold,
+            total_weight(&remapped_weights(multisig, &ctx.accounts.profile.owners)),
+        )?;
+        execute_set_owners(multisig, ctx.accounts.profile.owners.clone(), Some(ctx.accounts.profile.threshold))
+    }
+
+    // Caps the total lamports a single transaction may transfer out of its signer PDA. The only
+    // way this can be invoked is via a recursive call from execute_transaction.
+    pub fn set_max_sol_outflow(ctx: Context<Auth>, max_sol_outflow: u64) -> Result<()> {
+        ctx.accounts.multisig.max_sol_outflow = max_sol_outflow;
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(owners: Vec<Pubkey>, threshold: u64, nonce: u8)]
-pub struct CreateMultisig<'info> {
-    // see https://book.anchor-lang.com/anchor_references/space.html
-    #[account(
-        init,
-        space = multisig_data_len!(owners.len()),
-        payer = payer,
-        signer
-    )]
-    multisig: Box<Account<'info, Multisig>>,
-    /// CHECK: multisig_signer is a PDA program signer. Data is never read or written to
-    #[account(
-        seeds = [multisig.key().as_ref()],
-        bump = nonce,
-    )]
-    multisig_signer: UncheckedAccount<'info>,
-    #[account(mut)]
-    payer: Signer<'info>,
-    system_program: Program<'info, System>,
-}
+    // Bounds how long after creation a transaction may remain executable. The only
+    // way this can be invoked is via a recursive call from execute_transaction.
+    pub fn set_max_pending_secs(ctx: Context<Auth>, max_pending_secs: i64) -> Result<()> {
+        require!(max_pending_secs > 0, ErrorCode::InvalidMaxPendingSecs);
+        ctx.accounts.multisig.max_pending_secs = max_pending_secs;
+        Ok(())
+    }
 
-#[derive(Accounts)]
-#[instruction(instructions: Vec<TransactionInstruction>)]
-pub struct CreateTransaction<'info> {
-    multisig: Box<Account<'info, Multisig>>,
-    // see https://book.anchor-lang.com/anchor_references/space.html
-    #[account(
-        init,
-        space = transaction_data_len!(instructions, multisig.owners.len()),
-        payer = payer,
-        signer
-    )]
-    transaction: Box<Account<'info, Transaction>>,
-    // One of the owners. Checked in the handler.
-    proposer: Signer<'info>,
-    #[account(mut)]
-    payer: Signer<'info>,
-    system_program: Program<'info, System>,
-}
+    // Sets how long after creation a transaction proposed without an explicit expiry
+    // expires. The only way this can be invoked is via a recursive call from execute_transaction.
+    pub fn set_default_expiry_secs(ctx: Context<Auth>, default_expiry_secs: i64) -> Result<()> {
+        require!(default_expiry_secs > 0, ErrorCode::InvalidDefaultExpirySecs);
+        ctx.accounts.multisig.default_expiry_secs = default_expiry_secs;
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct Approve<'info> {
-    #[account(constraint = multisig.owner_set_seqno == transaction.owner_set_seqno)]
-    multisig: Box<Account<'info, Multisig>>,
-    #[account(mut, has_one = multisig)]
-    transaction: Box<Account<'info, Transaction>>,
-    // One of the multisig owners. Checked in the handler.
-    owner: Signer<'info>,
-}
+    // Sets the minimum seconds between owner set changes. The only way this can be
+    // invoked is via a recursive call from execute_transaction.
+    pub fn set_owner_change_cooldown(ctx: Context<Auth>, owner_change_cooldown: i64) -> Result<()> {
+        ctx.accounts.multisig.owner_change_cooldown = owner_change_cooldown;
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct Auth<'info> {
-    #[account(mut)]
-    multisig: Box<Account<'info, Multisig>>,
-    #[account(
-        seeds = [multisig.key().as_ref()],
-        bump = multisig.nonce,
-    )]
-    multisig_signer: Signer<'info>,
-}
+    // Sets the seconds an owner must wait after being added before they may approve. The only
+    // way this can be invoked is via a recursive call from execute_transaction.
+    pub fn set_new_owner_delay(ctx: Context<Auth>, new_owner_delay: i64) -> Result<()> {
+        ctx.accounts.multisig.new_owner_delay = new_owner_delay;
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct ExecuteTransaction<'info> {
-    #[account(constraint = multisig.owner_set_seqno == transaction.owner_set_seqno)]
-    multisig: Box<Account<'info, Multisig>>,
-    /// CHECK: multisig_signer is a PDA program signer. Data is never read or written to
-    #[account(
-        seeds = [multisig.key().as_ref()],
-        bump = multisig.nonce,
-    )]
-    multisig_signer: UncheckedAccount<'info>,
-    #[account(mut, has_one = multisig, close = refundee)]
-    transaction: Box<Account<'info, Transaction>>,
-    /// CHECK: success can be any address where rent exempt funds are sent
-    #[account(mut)]
-    refundee:  AccountInfo<'info>,
-    executor: Signer<'info>,
-}
+    // Sets the seconds after an owner change during which transactions which had reached their
+    // threshold under the previous owners may still be executed. The only way this can be invoked
+    // is via a recursive call from execute_transaction.
+    pub fn set_churn_grace_secs(ctx: Context<Auth>, churn_grace_secs: i64) -> Result<()> {
+        ctx.accounts.multisig.churn_grace_secs = churn_grace_secs;
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct CancelTransaction<'info> {
-    #[account(constraint = multisig.owner_set_seqno >= transaction.owner_set_seqno)]
-    multisig: Box<Account<'info, Multisig>>,
-    #[account(mut, has_one = multisig, close = refundee)]
+    // Sets the keys, besides the owners, allowed to execute transactions that have
+    // enough approvals. The only way this can be invoked is via a recursive call from
+    // execute_transaction.
+    pub fn set_executors(ctx: Context<Auth>, executors: Vec<Pubkey>) -> Result<()> {
+        require!(executors.len() <= MAX_EXECUTORS, ErrorCode::TooManyExecutors);
+        ctx.accounts.multisig.executors = executors;
+        Ok(())
+    }
+
+    // Sets whether anyone, not just the owners and executors, may execute transactions that have
+    // enough approvals, e.g. keepers collecting their reward. The only way this can be invoked is
+    // via a recursive call from execute_transaction.
+    pub fn set_public_execution(ctx: Context<Auth>, public_execution: bool) -> Result<()> {
+        ctx.accounts.multisig.public_execution = public_execution;
+        Ok(())
+    }
+
+    // Sets the seconds after creation during which approvals of a transaction don't count towards
+    // executing it, so every owner gets to see a proposal before it can be rushed through. The
+    // only way this can be invoked is via a recursive call from execute_transaction.
+    pub fn set_quarantine_secs(ctx: Context<Auth>, quarantine_secs: i64) -> Result<()> {
+        ctx.accounts.multisig.quarantine_secs = quarantine_secs;
+        Ok(())
+    }
+
+    // Sets whether executing a transaction is kept separate from proposing and approving it: the
+    // proposer may not execute it, nor may an executor who is its only approver. The only way
+    // this can be invoked is via a recursive call from execute_transaction.
+    pub fn set_strict_separation(ctx: Context<Auth>, strict_separation: bool) -> Result<()> {
+        ctx.accounts.multisig.strict_separation = strict_separation;
+        Ok(())
+    }
+
+    // Sets the lower threshold of transactions whose instructions write to no account, or 0 to
+    // apply `threshold` to every transaction. The only way this can be invoked is via a recursive
+    // call from execute_transaction.
+    pub fn set_read_threshold(ctx: Context<Auth>, read_threshold: u64) -> Result<()> {
+        ctx.accounts.multisig.read_threshold = read_threshold;
+        Ok(())
+    }
+
+    // Sets how many approvals stop owners other than the proposer cancelling an unexpired
+    // transaction, or 0 to let any owner cancel any transaction. The only way this can be invoked
+    // is via a recursive call from execute_transaction.
+    pub fn set_cancel_approval_limit(ctx: Context<Auth>, cancel_approval_limit: u8) -> Result<()> {
+        ctx.accounts.multisig.cancel_approval_limit = cancel_approval_limit;
+        Ok(())
+    }
+
+    // Sets how many owners must approve cancelling an unexpired transaction, the owner cancelling
+    // it included, 1 letting any owner cancel on their own. The only way this can be invoked is
+    // via a recursive call from execute_transaction.
+    pub fn set_cancel_threshold(ctx: Context<Auth>, cancel_threshold: u64) -> Result<()> {
+        assert_valid_cancel_threshold(cancel_threshold, ctx.accounts.multisig.owners.len())?;
+        ctx.accounts.multisig.cancel_threshold = cancel_threshold;
+        Ok(())
+    }
+
+    // Sets the threshold of transactions changing the owners, their weights or the threshold,
+    // which may not be lower than the threshold, or 0 to apply `threshold` to them too. Under stake
+    // weighting the admin stake threshold applies instead, so must be set too. The only way this
+    // can be invoked is via a recursive call from execute_transaction.
+    pub fn set_admin_threshold(ctx: Context<Auth>, admin_threshold: u64) -> Result<()> {
+        assert_valid_admin_threshold(admin_threshold, ctx.accounts.multisig.threshold)?;
+        ctx.accounts.multisig.admin_threshold = admin_threshold;
+        assert_valid_admin_stake_threshold(&ctx.accounts.multisig)
+    }
+
+    // Sets the lamports a single owner may transfer per day with execute_within_limit, 0 to
+    // disable it. The only way this can be invoked is via a recursive call from
+    // execute_transaction.
+    pub fn set_daily_limit(ctx: Context<Auth>, daily_limit: u64) -> Result<()> {
+        ctx.accounts.multisig.daily_limit = daily_limit;
+        Ok(())
+    }
+
+    // Sets the most instructions a transaction may have, and the most data bytes any one of them
+    // may have, checked when transactions are created. 0 leaves either limited only by what fits
+    // in a transaction account. The only way this can be invoked is via a recursive call from
+    // execute_transaction.
+    pub fn set_instruction_limits(ctx: Context<Auth>, max_instructions: u16, max_data_len: u16) -> Result<()> {
+        ctx.accounts.multisig.max_instructions = max_instructions;
+        ctx.accounts.multisig.max_data_len = max_data_len;
+        Ok(())
+    }
+
+    // Sets how much each owner's approval counts towards the threshold, which they must be able
+    // to reach together. The only way this can be invoked is via a recursive call from
+    // execute_transaction.
+    pub fn set_owner_weights(ctx: Context<Auth>, weights: Vec<u64>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        assert_valid_weights(&weights, multisig.owners.len())?;
+        assert_valid_threshold(multisig.threshold, total_weight(&weights))?;
+        multisig.weights = weights;
+        assert_no_single_owner_majority(multisig)
+    }
+
+    // Sets whether to reject weights and thresholds letting a single owner meet the threshold on
+    // their own, checking the current ones when set. The only way this can be invoked is via a
+    // recursive call from execute_transaction.
+    pub fn set_no_single_owner_majority(ctx: Context<Auth>, no_single_owner_majority: bool) -> Result<()> {
+        ctx.accounts.multisig.no_single_owner_majority = no_single_owner_majority;
+        assert_no_single_owner_majority(&ctx.accounts.multisig)
+    }
+
+    // Sets the upgradeable programs transactions may only call while their deployed code hashes
+    // to the pinned value. The only way this can be invoked is via a recursive call from
+    // execute_transaction.
+    pub fn set_pinned_programs(ctx: Context<Auth>, pinned_programs: Vec<ProgramPin>) -> Result<()> {
+        require!(pinned_programs.len() <= MAX_PINNED_PROGRAMS, ErrorCode::TooManyPinnedPrograms);
+        ctx.accounts.multisig.pinned_programs = pinned_programs;
+        Ok(())
+    }
+
+    // Restricts the programs transactions may call to the given ones, checked when transactions
+    // are created. An empty list allows any program. The only way this can be invoked is via a
+    // recursive call from execute_transaction.
+    pub fn set_allowed_programs(ctx: Context<Auth>, programs: Vec<Pubkey>) -> Result<()> {
+        require!(programs.len() <= MAX_ALLOWED_PROGRAMS, ErrorCode::TooManyAllowedPrograms);
+        ctx.accounts.multisig.allowed_programs = programs;
+        Ok(())
+    }
+
+    // Weighs each owner's approval by the live balance of a token account, one per owner in
+    // order, and requires approvals weighing at least `stake_threshold` rather than `threshold`
+    // approvals, and `admin_stake_threshold` rather than `admin_threshold` for admin changes, 0
+    // to apply `stake_threshold` to them too. Empty `stake_accounts` goes back to counting
+    // approvals. The only way this can be invoked is via a recursive call from execute_transaction.
+    pub fn set_stake_weighting(
+        ctx: Context<Auth>,
+        stake_accounts: Vec<Pubkey>,
+        stake_threshold: u64,
+        admin_stake_threshold: u64,
+    ) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        require!(
+            stake_accounts.is_empty() || (stake_accounts.len() == multisig.owners.len() && stake_threshold > 0),
+            ErrorCode::InvalidStakeWeighting
+        );
+        multisig.stake_accounts = stake_accounts;
+        multisig.stake_threshold = stake_threshold;
+        multisig.admin_stake_threshold = admin_stake_threshold;
+        assert_valid_admin_stake_threshold(multisig)
+
+    }
+
+    // Sets whether the payer of a new transaction account must be someone other than its
+    // proposer. The only way this can be invoked is via a recursive call from execute_transaction.
+    pub fn set_require_distinct_payer_proposer(ctx: Context<Auth>, require_distinct_payer_proposer: bool) -> Result<()> {
+        ctx.accounts.multisig.require_distinct_payer_proposer = require_distinct_payer_proposer;
+        Ok(())
+    }
+
+    // Sets whether transactions may not transfer so much SOL out of their signer PDA that it
+    // drops below rent-exemption. The only way this can be invoked is via a recursive call
+    // from execute_transaction.
+    pub fn set_require_signer_rent_exemption(ctx: Context<Auth>, require_signer_rent_exemption: bool) -> Result<()> {
+        ctx.accounts.multisig.require_signer_rent_exemption = require_signer_rent_exemption;
+        Ok(())
+    }
+
+    // Sets whether the threshold is capped at the number of owners whose signing rights haven't
+    // expired. The only way this can be invoked is via a recursive call from execute_transaction.
+    pub fn set_threshold_against_active(ctx: Context<Auth>, threshold_against_active: bool) -> Result<()> {
+        ctx.accounts.multisig.threshold_against_active = threshold_against_active;
+        Ok(())
+    }
+
+    // Sets whether transactions proposed while the threshold is measured against active owners
+    // keep the number of approvals required when they were proposed, rather than following owners
+    // expiring. The only way this can be invoked is via a recursive call from execute_transaction.
+    pub fn set_snapshot_quorum(ctx: Context<Auth>, snapshot_quorum: bool) -> Result<()> {
+        ctx.accounts.multisig.snapshot_quorum = snapshot_quorum;
+        Ok(())
+    }
+
+    // Closes every transaction of the multisig passed in the remaining accounts, refunding their
+    // rent to the refundee, e.g. to sweep a queue flooded by a compromised owner. The only way
+    // this can be invoked is via a recursive call from execute_transaction.
+    pub fn bulk_cancel<'info>(ctx: Context<'_, '_, 'info, 'info, BulkCancel<'info>>) -> Result<()> {
+        for info in ctx.remaining_accounts.iter() {
+            let transaction = Account::<Transaction>::try_from(info)?;
+            require_keys_eq!(transaction.multisig, ctx.accounts.multisig.key(), ErrorCode::InvalidTransaction);
+            // Executed transactions include the one running this instruction, which closes itself.
+            require!(!has_started_execution(&transaction), ErrorCode::AlreadyExecuted);
+            release_owner_change(&mut ctx.accounts.multisig, &transaction);
+            update_stats(&mut ctx.accounts.stats, |stats| {
+                stats.transactions_cancelled = stats.transactions_cancelled.saturating_add(1)
+            });
+            record_audit_entry(
+                &mut ctx.accounts.audit_log,
+                AuditAction::Cancel,
+                ctx.accounts.multisig_signer.key,
+                &transaction.key(),
+            )?;
+            transaction.close(ctx.accounts.refundee.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    // Rearranges the owners, and everything recorded per owner, so the owner at `new_order[index]`
+    // moves to `index`. The owner set is the same but approvals are recorded by index, so the owner
+    // set sequence number is bumped as for any owner change. Pending transactions passed in the
+    // remaining accounts have their approvals rearranged to match and carry on under the new
+    // sequence number. The only way this can be invoked is via a recursive call from
+    // execute_transaction.
+    pub fn reorder_owners<'info>(ctx: Context<'_, '_, 'info, 'info, Auth<'info>>, new_order: Vec<u8>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let new_order: Vec<usize> = new_order.iter().map(|index| *index as usize).collect();
+        require!(is_permutation(&new_order, multisig.owners.len()), ErrorCode::InvalidOwnerOrder);
+
+        let previous_seqno = multisig.owner_set_seqno;
+        multisig.owners = permuted(&multisig.owners, &new_order);
+        multisig.owner_expiries = permuted(&multisig.owner_expiries, &new_order);
+        multisig.owner_added_at = permuted(&multisig.owner_added_at, &new_order);
+        multisig.owner_roles = permuted(&multisig.owner_roles, &new_order);
+        multisig.weights = permuted(&multisig.weights, &new_order);
+        if !multisig.stake_accounts.is_empty() {
+            multisig.stake_accounts = permuted(&multisig.stake_accounts, &new_order);
+        }
+        multisig.owners_hash = hash_owners(&multisig.owners);
+        multisig.owner_set_seqno = next_owner_set_seqno(multisig.owner_set_seqno)?;
+        multisig.open_owner_changes = 0;
+        record_owner_set(multisig, Clock::get()?.unix_timestamp);
+
+        for info in ctx.remaining_accounts.iter() {
+            let mut transaction = Account::<Transaction>::try_from(info)?;
+            require_keys_eq!(transaction.multisig, multisig.key(), ErrorCode::InvalidTransaction);
+            require!(!has_started_execution(&transaction), ErrorCode::AlreadyExecuted);
+            require!(
+                transaction.owner_set_seqno == previous_seqno && transaction.signers.len() == new_order.len(),
+                ErrorCode::InvalidTransaction
+            );
+            transaction.signers = permuted(&transaction.signers, &new_order);
+            transaction.cancel_signers = permuted(&transaction.cancel_signers, &new_order);
+            transaction.owner_set_seqno = multisig.owner_set_seqno;
+            if is_owner_change(&transaction.instructions) {
+                multisig.open_owner_changes = multisig.open_owner_changes.saturating_add(1);
+            }
+            transaction.exit(ctx.program_id)?;
+        }
+        Ok(())
+    }
+
+    // Sets how many proposals changing the owners may be open at once, 0 for no limit. The only
+    // way this can be invoked is via a recursive call from execute_transaction.
+    pub fn set_max_open_owner_changes(ctx: Context<Auth>, max_open_owner_changes: u8) -> Result<()> {
+        ctx.accounts.multisig.max_open_owner_changes = max_open_owner_changes;
+        Ok(())
+    }
+
+    // Sets whether every proposal must carry a memo. The only way this can be invoked is via a
+    // recursive call from execute_transaction.
+    pub fn set_require_memo(ctx: Context<Auth>, require_memo: bool) -> Result<()> {
+        ctx.accounts.multisig.require_memo = require_memo;
+        Ok(())
+    }
+
+    // Sets whether a repeated approval from the same owner is rejected rather than ignored. The
+    // only way this can be invoked is via a recursive call from execute_transaction.
+    pub fn set_reject_duplicate_approvals(ctx: Context<Auth>, reject_duplicate_approvals: bool) -> Result<()> {
+        ctx.accounts.multisig.reject_duplicate_approvals = reject_duplicate_approvals;
+        Ok(())
+    }
+
+    // Resizes the approvals of a transaction to the current number of owners, keeping existing
+    // approvals by index, or clears them all if the owners changed since it was proposed. The
+    // only way this can be invoked is via a recursive call from execute_transaction.
+    pub fn repair_signers(ctx: Context<RepairSigners>) -> Result<()> {
+        let transaction = &mut ctx.accounts.transaction;
+        let owner_set_matches = transaction.owner_set_seqno == ctx.accounts.multisig.owner_set_seqno;
+        transaction.signers = repaired_signers(&transaction.signers, ctx.accounts.multisig.owners.len(), owner_set_matches);
+        transaction.cancel_signers = repaired_signers(&transaction.cancel_signers, ctx.accounts.multisig.owners.len(), owner_set_matches);
+        Ok(())
+    }
+
+    // Sets when an owner's signing rights lapse, or removes the expiry with `None`. Expired
+    // owners can't approve and their approvals stop counting towards the threshold. The only
+    // way this can be invoked is via a recursive call from execute_transaction.
+    pub fn set_owner_expiry(ctx: Context<Auth>, owner: Pubkey, expires_at: Option<i64>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let owner_index = multisig.owners.iter()
+            .position(|a| *a == owner)
+            .ok_or(ErrorCode::InvalidOwner)?;
+        multisig.owner_expiries[owner_index] = expires_at;
+        Ok(())
+    }
+
+    // Registers the break-glass template of the multisig: instructions which the owner `executor`
+    // alone may execute, `delay_secs` after announcing it with arm_break_glass. The template may not
+    // make admin changes, which would let a single owner bypass the admin threshold. To replace a
+    // registered template, close it with close_break_glass first, e.g. earlier in the same
+    // transaction. The only way this can be invoked is via a recursive call from execute_transaction.
+    pub fn register_break_glass(
+        ctx: Context<RegisterBreakGlass>,
+        instructions: Vec<TransactionInstruction>,
+        executor: Pubkey,
+        delay_secs: i64,
+    ) -> Result<()> {
+        require!(!instructions.is_empty(), ErrorCode::MissingInstructions);
+        require!(!is_admin_change(&instructions), ErrorCode::BreakGlassAdminChange);
+        require!(delay_secs > 0, ErrorCode::InvalidBreakGlassDelay);
+        require!(ctx.accounts.multisig.owners.contains(&executor), ErrorCode::InvalidOwner);
+
+        let break_glass = &mut ctx.accounts.break_glass;
+        break_glass.multisig = ctx.accounts.multisig.key();
+        break_glass.instructions_hash = hash_instructions(&instructions)?;
+        break_glass.executor = executor;
+        break_glass.delay_secs = delay_secs;
+        break_glass.armed_at = 0;
+        Ok(())
+    }
+
+    // Removes the break-glass template of the multisig, disarming it and refunding its rent to the
+    // refundee. The only way this can be invoked is via a recursive call from execute_transaction.
+    pub fn close_break_glass(_ctx: Context<CloseBreakGlass>) -> Result<()> {
+        Ok(())
+    }
+
+    // Starts the break-glass delay, announcing the intent to execute the template.
+    pub fn arm_break_glass(ctx: Context<ArmBreakGlass>) -> Result<()> {
+        let break_glass = &mut ctx.accounts.break_glass;
+        require!(
+            *ctx.accounts.executor.key == break_glass.executor && ctx.accounts.multisig.owners.contains(ctx.accounts.executor.key),
+            ErrorCode::InvalidExecutor
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        break_glass.armed_at = now;
+        msg!("BREAK GLASS ARMED for multisig {}", break_glass.multisig);
+        emit!(BreakGlassArmed {
+            multisig: break_glass.multisig,
+            executor: break_glass.executor,
+            executable_at: now.saturating_add(break_glass.delay_secs),
+        });
+        Ok(())
+    }
+
+    // Executes the break-glass template, bypassing the threshold, once its delay has passed
+    // since it was armed. The instructions must be those registered.
+    pub fn emergency_execute(ctx: Context<EmergencyExecute>, instructions: Vec<TransactionInstruction>) -> Result<()> {
+        let break_glass = &mut ctx.accounts.break_glass;
+        require!(
+            *ctx.accounts.executor.key == break_glass.executor && ctx.accounts.multisig.owners.contains(ctx.accounts.executor.key),
+            ErrorCode::InvalidExecutor
+        );
+        assert_not_frozen(&ctx.accounts.multisig, &instructions)?;
+        require!(break_glass.armed_at != 0, ErrorCode::BreakGlassNotArmed);
+        require!(
+            Clock::get()?.unix_timestamp >= break_glass.armed_at.saturating_add(break_glass.delay_secs),
+            ErrorCode::BreakGlassDelayNotElapsed
+        );
+        require!(hash_instructions(&instructions)? == break_glass.instructions_hash, ErrorCode::BreakGlassMismatch);
+
+        // Disarm before any CPI, as execute_transaction marks transactions executed.
+        break_glass.armed_at = 0;
+        break_glass.exit(ctx.program_id)?;
+
+        msg!("BREAK GLASS EXECUTED for multisig {}", break_glass.multisig);
+        emit!(BreakGlassExecuted {
+            multisig: break_glass.multisig,
+            executor: break_glass.executor,
+        });
+
+        let multisig_key = ctx.accounts.multisig.key();
+        let bump = [ctx.accounts.multisig.nonce];
+        let seeds: [&[u8]; 2] = [multisig_key.as_ref(), &bump];
+        invoke_instructions(&instructions, ctx.accounts.multisig_signer.key, &seeds, ctx.remaining_accounts)
+    }
+
+    // Executes the given transaction if threshold owners have signed it.
+    pub fn execute_transaction(ctx: Context<ExecuteTransaction>) -> Result<()> {
+        execute_approved(
+            ctx.program_id,
+            &ctx.accounts.multisig,
+            ctx.accounts.multisig_signer.key,
+            &mut ctx.accounts.transaction,
+            &ctx.accounts.refundee,
+            &ctx.accounts.executor,
+            &mut ctx.accounts.audit_log,
+            &mut ctx.accounts.stats,
+            ctx.remaining_accounts,
+            Execution::Remaining,
+        )
+    }
+
+    // Executes only the instruction at `index` of the given transaction, for transactions with
+    // too many instructions to execute within the compute budget of one call. The index must be
+    // that of the first instruction not yet executed, unless the transaction was created allowing
+    // out of order execution. Threshold and the other guards are checked on each call, and the
+    // transaction is only closed once every instruction has executed.
+    pub fn execute_transaction_by_index(ctx: Context<ExecuteTransaction>, index: u8) -> Result<()> {
+        execute_approved(
+            ctx.program_id,
+            &ctx.accounts.multisig,
+            ctx.accounts.multisig_signer.key,
+            &mut ctx.accounts.transaction,
+            &ctx.accounts.refundee,
+            &ctx.accounts.executor,
+            &mut ctx.accounts.audit_log,
+            &mut ctx.accounts.stats,
+            ctx.remaining_accounts,
+            Execution::Index(index),
+        )
+    }
+
+    // Executes a transaction made of a single SOL transfer from the multisig signer with the
+    // approval of just one owner, its proposer unless they chose not to approve, as long as the
+    // transfer fits within what is left of the daily limit. The limit resets at each UTC day
+    // boundary. SPL token transfers aren't covered: the limit is in lamports, against which
+    // amounts of an arbitrary mint can't be weighed, so they need the threshold like any other.
+    pub fn execute_within_limit(ctx: Context<ExecuteWithinLimit>) -> Result<()> {
+        let lamports = match ctx.accounts.transaction.instructions.as_slice() {
+            [ix] => system_transfer_lamports(ix, ctx.accounts.multisig_signer.key),
+            _ => None,
+        }
+        .ok_or(ErrorCode::NotASingleTransfer)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let multisig = &mut ctx.accounts.multisig;
+        if !is_same_day(now, multisig.last_reset) {
+            multisig.spent_today = 0;
+            multisig.last_reset = now;
+        }
+        let spent_today = multisig.spent_today.checked_add(lamports).ok_or(ErrorCode::Overflow)?;
+        require!(spent_today <= multisig.daily_limit, ErrorCode::DailyLimitExceeded);
+        multisig.spent_today = spent_today;
+
+        execute_approved(
+            ctx.program_id,
+            &ctx.accounts.multisig,
+            ctx.accounts.multisig_signer.key,
+            &mut ctx.accounts.transaction,
+            &ctx.accounts.refundee,
+            &ctx.accounts.executor,
+            &mut ctx.accounts.audit_log,
+            &mut ctx.accounts.stats,
+            ctx.remaining_accounts,
+            Execution::WithinLimit,
+        )
+    }
+
+    // Keeps the transaction account, rather than closing it, once executed. Only the proposer
+    // may decide this.
+    pub fn mark_retain(ctx: Context<MarkRetain>) -> Result<()> {
+        require_keys_eq!(ctx.accounts.transaction.proposer, ctx.accounts.proposer.key(), ErrorCode::NotProposer);
+        ctx.accounts.transaction.retain = true;
+        Ok(())
+    }
+
+    // Executes the transaction as part of the approval which reaches its threshold, rather than
+    // in a separate execute_transaction. Only the proposer may decide this.
+    pub fn mark_auto_execute(ctx: Context<MarkAutoExecute>) -> Result<()> {
+        require_keys_eq!(ctx.accounts.transaction.proposer, ctx.accounts.proposer.key(), ErrorCode::NotProposer);
+        ctx.accounts.transaction.auto_execute = true;
+        Ok(())
+    }
+
+    // Retracts a transaction no owner other than its proposer has approved, closing it to the
+    // refundee. Only the proposer may do this.
+    pub fn delete_transaction(ctx: Context<DeleteTransaction>) -> Result<()> {
+        require_keys_eq!(ctx.accounts.transaction.proposer, ctx.accounts.proposer.key(), ErrorCode::UnableToDelete);
+        let proposer_index = ctx.accounts.multisig.owners.iter().position(|a| a == ctx.accounts.proposer.key);
+        require!(
+            ctx.accounts.transaction.signers.iter().enumerate()
+                .all(|(owner_index, signed)| !signed || Some(owner_index) == proposer_index),
+            ErrorCode::TransactionAlreadySigned
+        );
+        release_owner_change(&mut ctx.accounts.multisig, &ctx.accounts.transaction);
+        update_stats(&mut ctx.accounts.stats, |stats| {
+            stats.transactions_cancelled = stats.transactions_cancelled.saturating_add(1)
+        });
+        record_audit_entry(
+            &mut ctx.accounts.audit_log,
+            AuditAction::Cancel,
+            ctx.accounts.proposer.key,
+            &ctx.accounts.transaction.key(),
+        )?;
+        emit!(TransactionCancelled {
+            multisig: ctx.accounts.multisig.key(),
+            transaction: ctx.accounts.transaction.key(),
+            executor: ctx.accounts.proposer.key(),
+            owner_set_seqno: ctx.accounts.transaction.owner_set_seqno,
+        });
+        Ok(())
+    }
+
+    // Approves cancelling the given transaction, for cancel_transaction once enough owners have.
+    pub fn approve_cancel(ctx: Context<ApproveCancel>) -> Result<()> {
+        let owner_index = ctx.accounts.multisig.owners.iter()
+            .position(|a| a == ctx.accounts.owner.key)
+            .ok_or(ErrorCode::InvalidOwner)?;
+        ctx.accounts.transaction.cancel_signers[owner_index] = true;
+        Ok(())
+    }
+
+    // Cancel the given transaction. Under a cancel approval limit, only its proposer may cancel an
+    // unexpired transaction with that many approvals. Under a cancel threshold, an unexpired
+    // transaction of the current owners also needs that many owners to approve cancelling it.
+    pub fn cancel_transaction(ctx: Context<CancelTransaction>) -> Result<()> {
+        let executor_index = ctx.accounts.multisig.owners.iter()
+            .position(|a| a == ctx.accounts.executor.key)
+            .ok_or(ErrorCode::InvalidExecutor)?;
+        let expired = Clock::get()?.unix_timestamp >= ctx.accounts.transaction.expires_at;
+        require!(
+            expired
+                || ctx.accounts.transaction.proposer == ctx.accounts.executor.key()
+                || !has_reached_cancel_approval_limit(&ctx.accounts.multisig, &ctx.accounts.transaction),
+            ErrorCode::TransactionAlreadySigned
+        );
+        require!(
+            expired
+                || ctx.accounts.multisig.owner_set_seqno != ctx.accounts.transaction.owner_set_seqno
+                || cancel_approval_count(&ctx.accounts.transaction, executor_index) >= ctx.accounts.multisig.cancel_threshold,
+            ErrorCode::NotEnoughCancelApprovals
+        );
+        release_owner_change(&mut ctx.accounts.multisig, &ctx.accounts.transaction);
+        update_stats(&mut ctx.accounts.stats, |stats| {
+            if expired {
+                stats.transactions_expired = stats.transactions_expired.saturating_add(1)
+            } else {
+                stats.transactions_cancelled = stats.transactions_cancelled.saturating_add(1)
+            }
+        });
+        record_audit_entry(
+            &mut ctx.accounts.audit_log,
+            AuditAction::Cancel,
+            ctx.accounts.executor.key,
+            &ctx.accounts.transaction.key(),
+        )?;
+        emit!(TransactionCancelled {
+            multisig: ctx.accounts.multisig.key(),
+            transaction: ctx.accounts.transaction.key(),
+            executor: ctx.accounts.executor.key(),
+            owner_set_seqno: ctx.accounts.transaction.owner_set_seqno,
+        });
+        Ok(())
+    }
+
+    // Creates the optional audit log of the multisig. Once it exists, passing it to the
+    // transaction lifecycle instructions records who did what and when.
+    pub fn create_audit_log(ctx: Context<CreateAuditLog>) -> Result<()> {
+        ctx.accounts.audit_log.multisig = ctx.accounts.multisig.key();
+        Ok(())
+    }
+
+    // Creates the optional stats account of the multisig. Once it exists, passing it to the
+    // transaction lifecycle instructions keeps running totals of their outcomes.
+    pub fn create_stats(ctx: Context<CreateStats>) -> Result<()> {
+        ctx.accounts.stats.multisig = ctx.accounts.multisig.key();
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(owners: Vec<Pubkey>, threshold: u64, nonce: u8)]
+pub struct CreateMultisig<'info> {
+    // see https://book.anchor-lang.com/anchor_references/space.html
+    #[account(
+        init,
+        space = multisig_data_len!(owners.len()),
+        payer = payer,
+        signer
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    /// CHECK: multisig_signer is a PDA program signer. Data is never read or written to
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = nonce,
+    )]
+    multisig_signer: UncheckedAccount<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(owners: Vec<Pubkey>, threshold: u64, nonce: u8, min_delay: i64, weights: Option<Vec<u64>>, seed: [u8; 32])]
+pub struct CreateMultisigWithSeed<'info> {
+    #[account(
+        init,
+        space = multisig_data_len!(owners.len()),
+        payer = payer,
+        seeds = [b"multisig", payer.key().as_ref(), seed.as_ref()],
+        bump
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    /// CHECK: multisig_signer is a PDA program signer. Data is never read or written to
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = nonce,
+    )]
+    multisig_signer: UncheckedAccount<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(config: MultisigConfig, nonce: u8)]
+pub struct CreateMultisigFromConfig<'info> {
+    #[account(
+        init,
+        space = multisig_data_len!(config.owners.len()),
+        payer = payer,
+        signer
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    /// CHECK: multisig_signer is a PDA program signer. Data is never read or written to
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = nonce,
+    )]
+    multisig_signer: UncheckedAccount<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    instructions: Vec<TransactionInstruction>,
+    purpose: u8,
+    expires_at: Option<i64>,
+    require_consistent_signers: bool,
+    designated_executor: Option<Pubkey>,
+    memo: String,
+    display_order: Vec<u16>,
+    co_multisigs: Vec<Pubkey>
+)]
+pub struct CreateTransaction<'info> {
+    #[account(mut)]
+    multisig: Box<Account<'info, Multisig>>,
+    // see https://book.anchor-lang.com/anchor_references/space.html
+    #[account(
+        init,
+        space = transaction_data_len!(instructions, multisig.owners.len(), memo.len(), display_order.len(), co_multisigs.len()),
+        payer = payer,
+        signer
+    )]
+    transaction: Box<Account<'info, Transaction>>,
+    // One of the owners. Checked in the handler.
+    proposer: Signer<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+    #[account(mut, seeds = [b"audit_log", multisig.key().as_ref()], bump)]
+    audit_log: Option<Account<'info, AuditLog>>,
+    #[account(mut, seeds = [b"stats", multisig.key().as_ref()], bump)]
+    stats: Option<Account<'info, Stats>>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_authority: Pubkey, memo: String)]
+pub struct SetProgramUpgradeAuthority<'info> {
+    #[account(mut)]
+    multisig: Box<Account<'info, Multisig>>,
+    /// CHECK: multisig_signer is a PDA program signer. Data is never read or written to
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: UncheckedAccount<'info>,
+    // see https://book.anchor-lang.com/anchor_references/space.html
+    #[account(
+        init,
+        space = transaction_data_len!(
+            [set_upgrade_authority_instruction(program.key, multisig_signer.key, &new_authority)],
+            multisig.owners.len(),
+            memo.len(),
+            0,
+            0
+        ),
+        payer = payer,
+        signer
+    )]
+    transaction: Box<Account<'info, Transaction>>,
+    /// CHECK: the upgradeable program whose authority is handed over. Only its address is used
+    program: UncheckedAccount<'info>,
+    // One of the owners. Checked in the handler.
+    proposer: Signer<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+    #[account(mut, seeds = [b"audit_log", multisig.key().as_ref()], bump)]
+    audit_log: Option<Account<'info, AuditLog>>,
+    #[account(mut, seeds = [b"stats", multisig.key().as_ref()], bump)]
+    stats: Option<Account<'info, Stats>>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, memo: String)]
+pub struct ProposeTokenTransfer<'info> {
+    #[account(mut)]
+    multisig: Box<Account<'info, Multisig>>,
+    /// CHECK: multisig_signer is a PDA program signer. Data is never read or written to
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: UncheckedAccount<'info>,
+    #[account(
+        init,
+        space = transaction_data_len!(
+            [token_transfer_instruction(token_program.key, source.key, destination.key, multisig_signer.key, amount)],
+            multisig.owners.len(),
+            memo.len(),
+            0,
+            0
+        ),
+        payer = payer,
+        signer
+    )]
+    transaction: Box<Account<'info, Transaction>>,
+    /// CHECK: the token program the tokens are held with. Checked in the handler
+    token_program: UncheckedAccount<'info>,
+    /// CHECK: the token account the tokens are transferred out of. Checked in the handler
+    source: UncheckedAccount<'info>,
+    /// CHECK: the token account the tokens are transferred to, checked by the token program on execution
+    destination: UncheckedAccount<'info>,
+    // One of the owners. Checked in the handler.
+    proposer: Signer<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+    #[account(mut, seeds = [b"audit_log", multisig.key().as_ref()], bump)]
+    audit_log: Option<Account<'info, AuditLog>>,
+    #[account(mut, seeds = [b"stats", multisig.key().as_ref()], bump)]
+    stats: Option<Account<'info, Stats>>,
+}
+
+#[derive(Accounts)]
+pub struct Approve<'info> {
+    #[account(constraint = multisig.owner_set_seqno == transaction.owner_set_seqno)]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(mut, has_one = multisig)]
+    transaction: Box<Account<'info, Transaction>>,
+    // One of the multisig owners. Checked in the handler. Paid the keeper reward of an
+    // auto-executing transaction.
+    #[account(mut)]
+    owner: Signer<'info>,
+    #[account(mut, seeds = [b"audit_log", multisig.key().as_ref()], bump)]
+    audit_log: Option<Account<'info, AuditLog>>,
+    // The remaining accounts are only needed to execute the transaction on approval.
+    /// CHECK: multisig_signer is a PDA program signer. Data is never read or written to
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: Option<UncheckedAccount<'info>>,
+    /// CHECK: success can be any address where rent exempt funds are sent
+    #[account(mut)]
+    refundee: Option<AccountInfo<'info>>,
+    #[account(mut, seeds = [b"stats", multisig.key().as_ref()], bump)]
+    stats: Option<Account<'info, Stats>>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveMany<'info> {
+    multisig: Box<Account<'info, Multisig>>,
+    // One of the multisig owners. Checked in the handler.
+    owner: Signer<'info>,
+    #[account(mut, seeds = [b"audit_log", multisig.key().as_ref()], bump)]
+    audit_log: Option<Account<'info, AuditLog>>,
+}
+
+#[derive(Accounts)]
+pub struct CoApprove<'info> {
+    #[account(mut)]
+    transaction: Box<Account<'info, Transaction>>,
+    co_multisig: Box<Account<'info, Multisig>>,
+    #[account(
+        seeds = [co_multisig.key().as_ref()],
+        bump = co_multisig.nonce,
+    )]
+    co_multisig_signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ViewMultisig<'info> {
+    multisig: Box<Account<'info, Multisig>>,
+}
+
+#[derive(Accounts)]
+pub struct ViewTransaction<'info> {
+    #[account(constraint = multisig.owner_set_seqno == transaction.owner_set_seqno)]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(has_one = multisig)]
+    transaction: Box<Account<'info, Transaction>>,
+}
+
+#[derive(Accounts)]
+pub struct CheckExecutable<'info> {
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(has_one = multisig)]
+    transaction: Box<Account<'info, Transaction>>,
+}
+
+#[derive(Accounts)]
+pub struct Auth<'info> {
+    #[account(mut)]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BulkCancel<'info> {
+    #[account(mut)]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: Signer<'info>,
+    /// CHECK: success can be any address where rent exempt funds are sent
+    #[account(mut)]
+    refundee: AccountInfo<'info>,
+    #[account(mut, seeds = [b"audit_log", multisig.key().as_ref()], bump)]
+    audit_log: Option<Account<'info, AuditLog>>,
+    #[account(mut, seeds = [b"stats", multisig.key().as_ref()], bump)]
+    stats: Option<Account<'info, Stats>>,
+}
+
+#[derive(Accounts)]
+pub struct RepairSigners<'info> {
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: Signer<'info>,
+    #[account(
+        mut,
+        has_one = multisig,
+        realloc = transaction_data_len!(
+            transaction.instructions,
+            multisig.owners.len(),
+            transaction.memo.len(),
+            transaction.display_order.len(),
+            transaction.co_multisigs.len()
+        ),
+        realloc::payer = payer,
+        realloc::zero = false,
+    )]
+    transaction: Box<Account<'info, Transaction>>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterBreakGlass<'info> {
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: Signer<'info>,
+    #[account(
+        init,
+        space = break_glass_data_len!(),
+        payer = payer,
+        seeds = [b"break_glass", multisig.key().as_ref()],
+        bump
+    )]
+    break_glass: Box<Account<'info, BreakGlass>>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseBreakGlass<'info> {
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: Signer<'info>,
+    #[account(
+        mut,
+        close = refundee,
+        seeds = [b"break_glass", multisig.key().as_ref()],
+        bump
+    )]
+    break_glass: Box<Account<'info, BreakGlass>>,
+    /// CHECK: success can be any address where rent exempt funds are sent
+    #[account(mut)]
+    refundee: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeThresholdChange<'info> {
+    #[account(mut)]
+    multisig: Box<Account<'info, Multisig>>,
+    // One of the multisig owners. Checked in the handler.
+    owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeWeakeningDelayChange<'info> {
+    #[account(mut)]
+    multisig: Box<Account<'info, Multisig>>,
+    // One of the multisig owners. Checked in the handler.
+    owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OwnerAction<'info> {
+    #[account(mut)]
+    multisig: Box<Account<'info, Multisig>>,
+    // One of the multisig owners. Checked in the handler.
+    owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptOwnership<'info> {
+    #[account(mut)]
+    multisig: Box<Account<'info, Multisig>>,
+    // One of the proposed owners. Checked in the handler.
+    pending_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseMultisig<'info> {
+    #[account(mut, close = refundee)]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: Signer<'info>,
+    /// CHECK: success can be any address where rent exempt funds are sent
+    #[account(mut)]
+    refundee: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_owners: Vec<Pubkey>)]
+pub struct AddOwners<'info> {
+    #[account(
+        mut,
+        // Never shrinks an account created with room to spare.
+        realloc = multisig_data_len!(multisig.owners.len() + new_owners.len()).max(multisig.to_account_info().data_len()),
+        realloc::payer = payer,
+        realloc::zero = false,
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: Signer<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SaveProfile<'info> {
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: Signer<'info>,
+    #[account(
+        init,
+        space = profile_data_len!(name.len(), multisig.owners.len()),
+        payer = payer,
+        seeds = [b"profile", multisig.key().as_ref(), name.as_bytes()],
+        bump
+    )]
+    profile: Box<Account<'info, Profile>>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RestoreProfile<'info> {
+    #[account(mut)]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: Signer<'info>,
+    #[account(has_one = multisig)]
+    profile: Box<Account<'info, Profile>>,
+}
+
+#[derive(Accounts)]
+pub struct ArmBreakGlass<'info> {
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(mut, has_one = multisig, seeds = [b"break_glass", multisig.key().as_ref()], bump)]
+    break_glass: Box<Account<'info, BreakGlass>>,
+    // The break-glass executor. Checked in the handler.
+    executor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyExecute<'info> {
+    multisig: Box<Account<'info, Multisig>>,
+    /// CHECK: multisig_signer is a PDA program signer. Data is never read or written to
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: UncheckedAccount<'info>,
+    #[account(mut, has_one = multisig, seeds = [b"break_glass", multisig.key().as_ref()], bump)]
+    break_glass: Box<Account<'info, BreakGlass>>,
+    // The break-glass executor. Checked in the handler.
+    executor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTransaction<'info> {
+    #[account(constraint = is_owner_set_current_or_grandfathered(&multisig, &transaction, Clock::get()?.unix_timestamp))]
+    multisig: Box<Account<'info, Multisig>>,
+    /// CHECK: multisig_signer is a PDA program signer. Data is never read or written to
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: UncheckedAccount<'info>,
+    // Closed to the refundee in the handler, unless retained.
+    #[account(mut, has_one = multisig)]
+    transaction: Box<Account<'info, Transaction>>,
+    /// CHECK: success can be any address where rent exempt funds are sent
+    #[account(mut)]
+    refundee:  AccountInfo<'info>,
+    // Paid the transaction's keeper reward.
+    #[account(mut)]
+    executor: Signer<'info>,
+    #[account(mut, seeds = [b"audit_log", multisig.key().as_ref()], bump)]
+    audit_log: Option<Account<'info, AuditLog>>,
+    #[account(mut, seeds = [b"stats", multisig.key().as_ref()], bump)]
+    stats: Option<Account<'info, Stats>>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteWithinLimit<'info> {
+    // Records what was spent against the daily limit.
+    #[account(mut, constraint = multisig.owner_set_seqno == transaction.owner_set_seqno)]
+    multisig: Box<Account<'info, Multisig>>,
+    /// CHECK: multisig_signer is a PDA program signer. Data is never read or written to
+    #[account(
+        seeds = [multisig.key().as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: UncheckedAccount<'info>,
+    // Closed to the refundee in the handler, unless retained.
+    #[account(mut, has_one = multisig)]
+    transaction: Box<Account<'info, Transaction>>,
+    /// CHECK: success can be any address where rent exempt funds are sent
+    #[account(mut)]
+    refundee:  AccountInfo<'info>,
+    // Paid the transaction's keeper reward.
+    #[account(mut)]
+    executor: Signer<'info>,
+    #[account(mut, seeds = [b"audit_log", multisig.key().as_ref()], bump)]
+    audit_log: Option<Account<'info, AuditLog>>,
+    #[account(mut, seeds = [b"stats", multisig.key().as_ref()], bump)]
+    stats: Option<Account<'info, Stats>>,
+}
+
+#[derive(Accounts)]
+pub struct MarkRetain<'info> {
+    #[account(mut)]
+    transaction: Box<Account<'info, Transaction>>,
+    proposer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MarkAutoExecute<'info> {
+    #[account(mut)]
+    transaction: Box<Account<'info, Transaction>>,
+    proposer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTransaction<'info> {
+    #[account(mut, constraint = multisig.owner_set_seqno >= transaction.owner_set_seqno)]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(mut, has_one = multisig, close = refundee)]
     transaction: Box<Account<'info, Transaction>>,
     /// CHECK: success can be any address where rent exempt funds are sent
     #[account(mut)]
     refundee:  AccountInfo<'info>,
     executor: Signer<'info>,
+    #[account(mut, seeds = [b"audit_log", multisig.key().as_ref()], bump)]
+    audit_log: Option<Account<'info, AuditLog>>,
+    #[account(mut, seeds = [b"stats", multisig.key().as_ref()], bump)]
+    stats: Option<Account<'info, Stats>>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveCancel<'info> {
+    #[account(constraint = multisig.owner_set_seqno == transaction.owner_set_seqno)]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(mut, has_one = multisig)]
+    transaction: Box<Account<'info, Transaction>>,
+    // One of the multisig owners. Checked in the handler.
+    owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DeleteTransaction<'info> {
+    #[account(mut, constraint = multisig.owner_set_seqno == transaction.owner_set_seqno)]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(mut, has_one = multisig, close = refundee)]
+    transaction: Box<Account<'info, Transaction>>,
+    /// CHECK: success can be any address where rent exempt funds are sent
+    #[account(mut)]
+    refundee: AccountInfo<'info>,
+    // The proposer of the transaction. Checked in the handler.
+    proposer: Signer<'info>,
+    #[account(mut, seeds = [b"audit_log", multisig.key().as_ref()], bump)]
+    audit_log: Option<Account<'info, AuditLog>>,
+    #[account(mut, seeds = [b"stats", multisig.key().as_ref()], bump)]
+    stats: Option<Account<'info, Stats>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateAuditLog<'info> {
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(
+        init,
+        space = audit_log_data_len!(),
+        payer = payer,
+        seeds = [b"audit_log", multisig.key().as_ref()],
+        bump
+    )]
+    audit_log: Account<'info, AuditLog>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateStats<'info> {
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(
+        init,
+        space = stats_data_len!(),
+        payer = payer,
+        seeds = [b"stats", multisig.key().as_ref()],
+        bump
+    )]
+    stats: Account<'info, Stats>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[account]
+pub struct Multisig {
+    pub owners: Vec<Pubkey>,
+    pub threshold: u64,
+    pub nonce: u8,
+    pub owner_set_seqno: u32,
+    // owner_expiries[index] is when multisig.owners[index] stops being able to sign, if ever.
+    pub owner_expiries: Vec<Option<i64>>,
+    // owner_added_at[index] is when multisig.owners[index] was added, 0 for the founding owners.
+    pub owner_added_at: Vec<i64>,
+    // Hash of the owners in order, see `hash_owners`. Lets clients cheaply check a cached owner list is current.
+    pub owners_hash: [u8; 32],
+    // Maximum lamports a single transaction may transfer out of its signer PDA.
+    pub max_sol_outflow: u64,
+    // Maximum seconds from creation until a transaction expires.
+    pub max_pending_secs: i64,
+    // Seconds from creation until a transaction proposed without an explicit expiry expires.
+    pub default_expiry_secs: i64,
+    // Minimum seconds between owner set changes.
+    pub owner_change_cooldown: i64,
+    // Unix timestamp of the last owner set change, 0 if the owners never changed.
+    pub last_owner_change_at: i64,
+    // Seconds an owner must wait after being added before they may approve.
+    pub new_owner_delay: i64,
+    // Seconds after an owner change during which transactions which had reached their threshold
+    // under the previous owners may still be executed, 0 to invalidate them straight away.
+    pub churn_grace_secs: i64,
+    // Keys which may execute, but not approve, transactions.
+    pub executors: Vec<Pubkey>,
+    // If set, proposers may not pay for their own transaction accounts.
+    pub require_distinct_payer_proposer: bool,
+    // If set, transactions may not leave their signer PDA below rent-exemption.
+    pub require_signer_rent_exemption: bool,
+    // If set, the threshold is capped at the number of owners whose signing rights haven't expired.
+    pub threshold_against_active: bool,
+    // If set along with threshold_against_active, transactions require the number of approvals
+    // required when they were proposed.
+    pub snapshot_quorum: bool,
+    // If set, every proposal must carry a memo.
+    pub require_memo: bool,
+    // If set, an owner approving a transaction they have already approved is an error.
+    pub reject_duplicate_approvals: bool,
+    // Upgradeable programs which transactions may only call at the pinned version.
+    pub pinned_programs: Vec<ProgramPin>,
+    // stake_accounts[index] is the token account whose balance weighs multisig.owners[index]'s
+    // approval, or empty when approvals are counted instead.
+    pub stake_accounts: Vec<Pubkey>,
+    // Total stake approvals must weigh, when stake_accounts is set.
+    pub stake_threshold: u64,
+    // If non-zero, the total stake approvals of admin changes must weigh, when stake_accounts is
+    // set. It stands in for admin_threshold, so must be set whenever that is.
+    pub admin_stake_threshold: u64,
+    // Seconds a lowered threshold waits before it can be finalized.
+    pub weakening_delay: i64,
+    // A lowered threshold waiting out the weakening delay, if any.
+    pub pending_threshold: Option<u64>,
+    // Unix timestamp from which pending_threshold can be finalized.
+    pub pending_threshold_ready_at: i64,
+    // A shortened weakening delay waiting out the current one, if any.
+    pub pending_weakening_delay: Option<i64>,
+    // Unix timestamp from which pending_weakening_delay can be finalized.
+    pub pending_weakening_delay_ready_at: i64,
+    // How many proposals changing the owners may be open at once, 0 for no limit.
+    pub max_open_owner_changes: u8,
+    // Proposals changing the owners created under the current owner set and not yet executed or
+    // cancelled.
+    pub open_owner_changes: u8,
+    // If set, anyone may execute transactions that have enough approvals.
+    pub public_execution: bool,
+    // Seconds after creation during which approvals of a transaction don't count yet.
+    pub quarantine_secs: i64,
+    // Seconds after creation before a transaction may be executed, fixed when the multisig is created.
+    pub min_delay: i64,
+    // If set, the proposer may not execute a transaction, nor may an executor who is its only approver.
+    pub strict_separation: bool,
+    // weights[index] is how much multisig.owners[index]'s approval counts towards the threshold.
+    pub weights: Vec<u64>,
+    // The most recent owner sets, the current one last, at most OWNER_HISTORY_CAPACITY of them.
+    pub owner_history: Vec<OwnerSetRecord>,
+    // The only programs transactions may call, or empty to allow any.
+    pub allowed_programs: Vec<Pubkey>,
+    // If non-zero, the threshold of transactions which write to no account, when lower than
+    // `threshold`.
+    pub read_threshold: u64,
+    // If non-zero, owners other than the proposer may only cancel an unexpired transaction with
+    // fewer approvals than this.
+    pub cancel_approval_limit: u8,
+    // If non-zero, the threshold of transactions changing the owners, their weights or the
+    // threshold, when higher than `threshold`.
+    pub admin_threshold: u64,
+    // Lamports a single owner may transfer per UTC day with execute_within_limit.
+    pub daily_limit: u64,
+    // Lamports transferred with execute_within_limit since `last_reset`.
+    pub spent_today: u64,
+    // Unix timestamp `spent_today` was last reset at, on the first spend of a new day.
+    pub last_reset: i64,
+    // If non-zero, the most instructions a transaction may have.
+    pub max_instructions: u16,
+    // If non-zero, the most data bytes any one instruction of a transaction may have.
+    pub max_data_len: u16,
+    // Owners proposed by propose_owners, replacing the owners once each has accepted.
+    pub pending_owners: Vec<Pubkey>,
+    // Whether each of the pending owners has accepted, by index.
+    pub pending_owner_accepted: Vec<bool>,
+    // Unix timestamp from which the pending owners can no longer accept.
+    pub pending_owners_expire_at: i64,
+    // Whether an owner has frozen execution, until the multisig unfreezes itself.
+    pub frozen: bool,
+    // How many owners must approve cancelling an unexpired transaction, the owner cancelling it
+    // included.
+    pub cancel_threshold: u64,
+    // Number of transactions ever created for the multisig, and so the `seq` of the next one.
+    pub transaction_count: u64,
+    // owner_roles[index] is the categories of transaction multisig.owners[index] may propose, as
+    // ROLE_* bits.
+    pub owner_roles: Vec<u8>,
+    // If set, no single owner's weight may meet the threshold on its own.
+    pub no_single_owner_majority: bool,
+}
+
+#[account]
+pub struct Transaction {
+    // The multisig account this transaction belongs to.
+    pub multisig: Pubkey,
+    // The instructions to be executed by this transaction
+    pub instructions: Vec<TransactionInstruction>,
+    // signers[index] is true iff multisig.owners[index] signed the transaction.
+    pub signers: Vec<bool>,
+    // Owner set sequence number.
+    pub owner_set_seqno: u32,
+    // Which of the multisig's signer PDAs executes the instructions, see `purpose_signer_address`.
+    pub purpose: u8,
+    // Set once execution of the last instruction not yet executed has started, guarding against
+    // re-execution.
+    pub did_execute: bool,
+    // Unix timestamp the transaction was created at.
+    pub created_at: i64,
+    // Unix timestamp from which the transaction can no longer be executed.
+    pub expires_at: i64,
+    // If set, the only key allowed to execute the transaction.
+    pub designated_executor: Option<Pubkey>,
+    // The owner who proposed the transaction.
+    pub proposer: Pubkey,
+    // If set, the account is kept rather than closed once executed.
+    pub retain: bool,
+    // Human-readable note on what the transaction is for.
+    pub memo: String,
+    // The order, by index into `instructions`, to display the instructions in. Empty for the
+    // order they are executed in.
+    pub display_order: Vec<u16>,
+    // Set once enough owners approved the transaction to execute it.
+    pub reached_threshold: bool,
+    // Other multisigs which must each approve the transaction before it can be executed.
+    pub co_multisigs: Vec<Pubkey>,
+    // co_approvals[index] is true iff co_multisigs[index] approved the transaction.
+    pub co_approvals: Vec<bool>,
+    // Lamports, held on top of rent, paid to whoever executes the transaction.
+    pub keeper_reward: u64,
+    // If set, the approval which reaches threshold also executes the transaction.
+    pub auto_execute: bool,
+    // executed_mask[index] is true iff instructions[index] has been executed.
+    pub executed_mask: Vec<bool>,
+    // If set, execute_transaction_by_index may execute the instructions in any order.
+    pub out_of_order: bool,
+    // Unix timestamp and slot at which execution of the last instruction started, or 0 until then.
+    pub executed_at: i64,
+    pub executed_slot: u64,
+    // cancel_signers[index] is true iff multisig.owners[index] approved cancelling the transaction.
+    pub cancel_signers: Vec<bool>,
+    // Index of the transaction among those created for the multisig, in the order they were created.
+    pub seq: u64,
+    // The approvals required when the transaction was proposed, if the multisig snapshots its
+    // quorum, otherwise 0 for them to be worked out when needed.
+    pub required_at_creation: u64,
+}
+
+#[account]
+pub struct BreakGlass {
+    // The multisig whose signer executes the template.
+    pub multisig: Pubkey,
+    // Hash of the template instructions, see `hash_instructions`.
+    pub instructions_hash: [u8; 32],
+    // The only owner who may arm and execute the template.
+    pub executor: Pubkey,
+    // Seconds between arming and executing the template.
+    pub delay_secs: i64,
+    // Unix timestamp the template was armed at, 0 if not armed.
+    pub armed_at: i64,
+}
+
+#[account]
+pub struct Profile {
+    // The multisig the profile was saved from.
+    pub multisig: Pubkey,
+    // Name the profile was saved under, also part of its address.
+    pub name: String,
+    // The saved owners and threshold.
+    pub owners: Vec<Pubkey>,
+    pub threshold: u64,
+}
+
+#[event]
+pub struct TransactionCreated {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub proposer: Pubkey,
+    pub owner_set_seqno: u32,
+    pub seq: u64,
+}
+
+#[event]
+pub struct TransactionApproved {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub owner: Pubkey,
+    pub owner_set_seqno: u32,
+}
+
+#[event]
+pub struct TransactionRevoked {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub owner: Pubkey,
+    pub owner_set_seqno: u32,
+}
+
+// Emitted once every instruction of the transaction has executed, whether in one call or by index.
+#[event]
+pub struct TransactionExecuted {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub executor: Pubkey,
+    pub owner_set_seqno: u32,
+    pub instruction_count: u16,
+    pub executed_at: i64,
+    pub executed_slot: u64,
+}
+
+// Emitted whenever the owners are replaced, with the threshold in force afterwards.
+#[event]
+pub struct OwnersChanged {
+    pub multisig: Pubkey,
+    pub previous_owners: Vec<Pubkey>,
+    pub owners: Vec<Pubkey>,
+    pub threshold: u64,
+    pub owner_set_seqno: u32,
+}
+
+#[event]
+pub struct TransactionCancelled {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub executor: Pubkey,
+    pub owner_set_seqno: u32,
+}
+
+#[event]
+pub struct BreakGlassArmed {
+    pub multisig: Pubkey,
+    pub executor: Pubkey,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct BreakGlassExecuted {
+    pub multisig: Pubkey,
+    pub executor: Pubkey,
+}
+
+#[account]
+pub struct Stats {
+    // The multisig whose transactions are counted.
+    pub multisig: Pubkey,
+    pub transactions_created: u64,
+    pub transactions_executed: u64,
+    // Transactions cancelled before expiring.
+    pub transactions_cancelled: u64,
+    // Transactions cancelled after expiring, i.e. cleaned up.
+    pub transactions_expired: u64,
+}
+
+#[account]
+pub struct AuditLog {
+    // The multisig whose transactions are recorded.
+    pub multisig: Pubkey,
+    // Most recent entries, oldest first, at most AUDIT_LOG_CAPACITY of them.
+    pub entries: Vec<AuditEntry>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AuditEntry {
+    pub action: AuditAction,
+    pub actor: Pubkey,
+    pub transaction: Pubkey,
+    pub timestamp: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Create,
+    Approve,
+    Execute,
+    Cancel,
+    Revoke,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct OwnerSetRecord {
+    pub owner_set_seqno: u32,
+    // See `hash_owners`.
+    pub owners_hash: [u8; 32],
+    // Unix timestamp the owner set took effect.
+    pub changed_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ProgramPin {
+    pub program_id: Pubkey,
+    // SHA-256 of the program's deployed code, see `assert_program_version`.
+    pub program_data_hash: [u8; 32],
+}
+
+// Settings of a single owner for configure_owner, each left as it is when `None`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct OwnerConfig {
+    pub weight: Option<u64>,
+    pub role: Option<u8>,
+    pub expires_at: Option<i64>,
+    // If set, removes the owner's expiry, `expires_at` being ignored.
+    pub remove_expiry: bool,
+}
+
+// The owners, threshold and configuration of a multisig, as documented on Multisig.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MultisigConfig {
+    pub owners: Vec<Pubkey>,
+    pub threshold: u64,
+    pub owner_expiries: Vec<Option<i64>>,
+    pub max_sol_outflow: u64,
+    pub max_pending_secs: i64,
+    pub default_expiry_secs: i64,
+    pub owner_change_cooldown: i64,
+    pub new_owner_delay: i64,
+    pub churn_grace_secs: i64,
+    pub executors: Vec<Pubkey>,
+    pub require_distinct_payer_proposer: bool,
+    pub require_signer_rent_exemption: bool,
+    pub threshold_against_active: bool,
+    pub snapshot_quorum: bool,
+    pub require_memo: bool,
+    pub reject_duplicate_approvals: bool,
+    pub pinned_programs: Vec<ProgramPin>,
+    pub stake_accounts: Vec<Pubkey>,
+    pub stake_threshold: u64,
+    pub admin_stake_threshold: u64,
+    pub weakening_delay: i64,
+    pub max_open_owner_changes: u8,
+    pub public_execution: bool,
+    pub quarantine_secs: i64,
+    pub min_delay: i64,
+    pub strict_separation: bool,
+    pub weights: Vec<u64>,
+    pub allowed_programs: Vec<Pubkey>,
+    pub read_threshold: u64,
+    pub cancel_approval_limit: u8,
+    pub admin_threshold: u64,
+    pub daily_limit: u64,
+    pub max_instructions: u16,
+    pub max_data_len: u16,
+    pub cancel_threshold: u64,
+    pub owner_roles: Vec<u8>,
+    pub no_single_owner_majority: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ApprovalReceipt {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub owner: Pubkey,
+    // The owner set the approval was given under.
+    pub owner_set_seqno: u32,
+    pub approved: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct OwnerApproval {
+    pub owner: Pubkey,
+    pub approved: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TransactionInstruction {
+    /// Pubkey of the program that executes this instruction.
+    pub program_id: Pubkey,
+    /// Metadata describing accounts that should be passed to the program.
+    pub accounts: Vec<TransactionAccount>,
+    /// Opaque data passed to the program for its own interpretation.
+    pub data: Vec<u8>,
+}
+
+impl From<&TransactionInstruction> for Instruction {
+    fn from(ix: &TransactionInstruction) -> Instruction {
+        Instruction {
+            program_id: ix.program_id,
+            accounts: ix.accounts.iter().map(Into::into).collect(),
+            data: ix.data.clone(),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TransactionAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl From<&TransactionAccount> for AccountMeta {
+    fn from(account: &TransactionAccount) -> AccountMeta {
+        match account.is_writable {
+            false => AccountMeta::new_readonly(account.pubkey, account.is_signer),
+            true => AccountMeta::new(account.pubkey, account.is_signer),
+        }
+    }
+}
+
+// Converts a standard instruction into the form stored in a transaction account. The inverse
+// of `Instruction::from(&TransactionInstruction)`.
+pub fn from_instruction(ix: &Instruction) -> TransactionInstruction {
+    TransactionInstruction {
+        program_id: ix.program_id,
+        accounts: ix.accounts.iter()
+            .map(|meta| TransactionAccount {
+                pubkey: meta.pubkey,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+            .collect(),
+        data: ix.data.clone(),
+    }
+}
+
+// Derives the additional signer PDA for the given purpose, seeded by `[multisig, purpose]`.
+// Purposes let the same owners govern separate signing identities (e.g. a "hot" and a
+// "cold" wallet). The default purpose is the original `multisig_signer` PDA instead.
+pub fn purpose_signer_address(multisig: &Pubkey, purpose: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[multisig.as_ref(), &[purpose]], &crate::ID)
+}
+
+// The PDA that signs transactions of the given purpose.
+fn transaction_signer_address(multisig: &Account<Multisig>, purpose: u8) -> Result<Pubkey> {
+    match purpose {
+        DEFAULT_PURPOSE => Ok(Pubkey::create_program_address(&[multisig.key().as_ref(), &[multisig.nonce]], &crate::ID)
+            .map_err(ProgramError::from)?),
+        _ => Ok(purpose_signer_address(&multisig.key(), purpose).0),
+    }
+}
+
+// Whether the owner at `owner_index` still holds signing rights at `now`.
+fn is_owner_active(multisig: &Multisig, owner_index: usize, now: i64) -> bool {
+    match multisig.owner_expiries.get(owner_index) {
+        Some(Some(expires_at)) => now < *expires_at,
+        _ => true,
+    }
+}
+
+// Whether the owner at `owner_index` was added too recently to approve at `now`.
+fn is_owner_cooling_off(multisig: &Multisig, owner_index: usize, now: i64) -> bool {
+    match multisig.owner_added_at.get(owner_index) {
+        Some(added_at) => now < added_at.saturating_add(multisig.new_owner_delay),
+        None => false,
+    }
+}
+
+// The combined weight of the approvals of the transaction by owners still active at `now`.
+fn approval_count(multisig: &Multisig, transaction: &Transaction, now: i64) -> u64 {
+    transaction.signers.iter()
+        .enumerate()
+        .filter(|(owner_index, &did_sign)| did_sign && is_owner_active(multisig, *owner_index, now))
+        .fold(0u64, |total, (owner_index, _)| total.saturating_add(owner_weight(multisig, owner_index)))
+}
+
+fn owner_weight(multisig: &Multisig, owner_index: usize) -> u64 {
+    multisig.weights.get(owner_index).copied().unwrap_or(1)
+}
+
+// Whether enough owners approved the transaction at `now` to execute it. When approvals are
+// weighed by stake, that is only known if the stake accounts are among `accounts`, so is
+// otherwise taken not to be.
+fn has_reached_threshold(multisig: &Multisig, transaction: &Transaction, now: i64, accounts: &[AccountInfo]) -> bool {
+    match multisig.stake_accounts.is_empty() {
+        true => approval_count(multisig, transaction, now) >= required_approvals(multisig, transaction, now),
+        false => approved_stake(multisig, transaction, now, accounts)
+            .is_ok_and(|stake| stake >= required_stake(multisig, transaction)),
+    }
+}
+
+// The total stake of the owners still active at `now` who approved the transaction, read from the
+// live balances of their registered token accounts, which must be passed in.
+fn approved_stake(multisig: &Multisig, transaction: &Transaction, now: i64, accounts: &[AccountInfo]) -> Result<u64> {
+    let mut stake = 0u64;
+    for (owner_index, stake_account) in multisig.stake_accounts.iter().enumerate() {
+        let did_sign = transaction.signers.get(owner_index).copied().unwrap_or(false);
+        if !did_sign || !is_owner_active(multisig, owner_index, now) || *stake_account == Pubkey::default() {
+            continue;
+        }
+        let info = accounts.iter()
+            .find(|info| info.key == stake_account)
+            .ok_or(ErrorCode::InvalidStakeAccount)?;
+        // Token account state: mint, owner, then the u64 amount.
+        let data = info.try_borrow_data()?;
+        require!([spl_token::ID, spl_token_2022::ID].contains(info.owner) && data.len() >= 72, ErrorCode::InvalidStakeAccount);
+        let amount = u64::from_le_bytes(data[64..72].try_into().unwrap());
+        stake = stake.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+    }
+    Ok(stake)
+}
+
+// The combined weight of approvals a transaction needs at `now` to be executed, always at least one.
+fn required_approvals(multisig: &Multisig, transaction: &Transaction, now: i64) -> u64 {
+    if transaction.required_at_creation > 0 {
+        return transaction.required_at_creation;
+    }
+    let threshold = if multisig.admin_threshold > 0 && is_admin_change(&transaction.instructions) {
+        multisig.threshold.max(multisig.admin_threshold)
+    } else if multisig.read_threshold > 0 && is_read_only(&transaction.instructions) {
+        multisig.threshold.min(multisig.read_threshold)
+    } else {
+        multisig.threshold
+    };
+    if !multisig.threshold_against_active {
+        return threshold;
+    }
+    let active_weight = (0..multisig.owners.len())
+        .filter(|owner_index| is_owner_active(multisig, *owner_index, now))
+        .fold(0u64, |total, owner_index| total.saturating_add(owner_weight(multisig, owner_index)));
+    threshold.min(active_weight).max(1)
+}
+
+// Whether the transaction has as many approvals as the multisig's cancel approval limit, if any.
+fn has_reached_cancel_approval_limit(multisig: &Multisig, transaction: &Transaction) -> bool {
+    let approvals = transaction.signers.iter().filter(|signed| **signed).count();
+    multisig.cancel_approval_limit > 0 && approvals >= multisig.cancel_approval_limit as usize
+}
+
+// How many owners approve cancelling the transaction, counting the one cancelling it.
+fn cancel_approval_count(transaction: &Transaction, executor_index: usize) -> u64 {
+    transaction.cancel_signers.iter()
+        .enumerate()
+        .filter(|(index, approved)| **approved || *index == executor_index)
+        .count() as u64
+}
+
+// Whether the two unix timestamps fall on the same UTC day.
+fn is_same_day(a: i64, b: i64) -> bool {
+    a.div_euclid(SECONDS_PER_DAY) == b.div_euclid(SECONDS_PER_DAY)
+}
+
+// Whether none of the instructions writes to any account.
+fn is_read_only(instructions: &[TransactionInstruction]) -> bool {
+    instructions.iter().all(|ix| ix.accounts.iter().all(|acc| !acc.is_writable))
+}
+
+// The earliest time at which the transaction may be executed, once out of both the quarantine and
+// the minimum delay.
+fn executable_at(multisig: &Multisig, transaction: &Transaction) -> i64 {
+    transaction.created_at.saturating_add(multisig.quarantine_secs.max(multisig.min_delay))
+}
+
+// Whether the transaction may be executed under the current owner set: it was proposed under it, or
+// had reached its threshold under the one just before it, which changed less than `churn_grace_secs` ago.
+fn is_owner_set_current_or_grandfathered(multisig: &Multisig, transaction: &Transaction, now: i64) -> bool {
+    multisig.owner_set_seqno == transaction.owner_set_seqno
+        || (transaction.reached_threshold
+            && transaction.owner_set_seqno.checked_add(1) == Some(multisig.owner_set_seqno)
+            && now < multisig.last_owner_change_at.saturating_add(multisig.churn_grace_secs))
+}
+
+// Whether the transaction has enough approvals to be executed. One approved by the previous
+// owners under churn protection already had, so counts as approved.
+fn has_quorum(multisig: &Multisig, transaction: &Transaction, now: i64, accounts: &[AccountInfo]) -> Result<bool> {
+    if multisig.owner_set_seqno != transaction.owner_set_seqno {
+        return Ok(true);
+    }
+    match multisig.stake_accounts.is_empty() {
+        true => Ok(approval_count(multisig, transaction, now) >= required_approvals(multisig, transaction, now)),
+        false => Ok(approved_stake(multisig, transaction, now, accounts)? >= required_stake(multisig, transaction)),
+    }
+}
+
+// The total stake of approvals a transaction needs to be executed under stake weighting.
+fn required_stake(multisig: &Multisig, transaction: &Transaction) -> u64 {
+    if multisig.admin_stake_threshold > 0 && is_admin_change(&transaction.instructions) {
+        multisig.stake_threshold.max(multisig.admin_stake_threshold)
+    } else {
+        multisig.stake_threshold
+    }
+}
+
+// Whether `who` may execute the transaction: its designated executor if it has one, otherwise any
+// owner or executor, or anyone at all under public execution.
+fn is_eligible_executor(multisig: &Multisig, transaction: &Transaction, who: &Pubkey) -> bool {
+    match transaction.designated_executor {
+        Some(designated_executor) => designated_executor == *who,
+        None => multisig.public_execution || multisig.owners.contains(who) || multisig.executors.contains(who),
+    }
+}
+
+// Whether the executor is neither the proposer of the transaction nor the only owner to approve it.
+fn is_separate_from_executor(multisig: &Multisig, transaction: &Transaction, executor: &Pubkey) -> bool {
+    transaction.proposer != *executor
+        && multisig.owners.iter()
+            .zip(transaction.signers.iter())
+            .any(|(owner, approved)| *approved && owner != executor)
+}
+
+// Which instructions of a transaction an execution runs, and the approvals it needs.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Execution {
+    // Every instruction not yet executed, with threshold approvals.
+    Remaining,
+    // Only the instruction at the index, with threshold approvals.
+    Index(u8),
+    // Every instruction, with a single approval, their spend having been checked against the
+    // daily limit.
+    WithinLimit,
+}
+
+// Approves a transaction on behalf of an owner, then executes it if it has reached its threshold
+// and is either auto-executing or `always_execute`. A repeated approval only executes it with the
+// latter. An auto-executing transaction which can't execute yet, for want of the accounts to
+// execute it with or of passing an execute-time guard, is only approved.
+fn approve_transaction(ctx: Context<Approve>, always_execute: bool) -> Result<()> {
+    let owner_index = ctx
+        .accounts
+        .multisig
+        .owners
+        .iter()
+        .position(|a| a == ctx.accounts.owner.key)
+        .ok_or(ErrorCode::InvalidOwner)?;
+    let now = Clock::get()?.unix_timestamp;
+    require!(is_owner_active(&ctx.accounts.multisig, owner_index, now), ErrorCode::OwnerExpired);
+    require!(!is_owner_cooling_off(&ctx.accounts.multisig, owner_index, now), ErrorCode::NewOwnerCoolingOff);
+    assert_not_frozen(&ctx.accounts.multisig, &ctx.accounts.transaction.instructions)?;
+    require!(!ctx.accounts.transaction.did_execute, ErrorCode::AlreadyExecuted);
+
+    // A repeated approval changes nothing, so is either rejected or reported and skipped.
+    if ctx.accounts.transaction.signers[owner_index] {
+        require!(!ctx.accounts.multisig.reject_duplicate_approvals, ErrorCode::AlreadyApproved);
+        msg!("Owner has already approved this transaction");
+        if !always_execute {
+            return Ok(());
+        }
+    } else {
+        ctx.accounts.transaction.signers[owner_index] = true;
+        ctx.accounts.transaction.reached_threshold = has_reached_threshold(&ctx.accounts.multisig, &ctx.accounts.transaction, now, ctx.remaining_accounts);
+
+        record_audit_entry(
+            &mut ctx.accounts.audit_log,
+            AuditAction::Approve,
+            ctx.accounts.owner.key,
+            &ctx.accounts.transaction.key(),
+        )?;
+        emit!(TransactionApproved {
+            multisig: ctx.accounts.multisig.key(),
+            transaction: ctx.accounts.transaction.key(),
+            owner: ctx.accounts.owner.key(),
+            owner_set_seqno: ctx.accounts.transaction.owner_set_seqno,
+        });
+    }
+
+    // The approving owner executes the transaction, subject to the same guards as
+    // execute_transaction.
+    // Stake weighted approvals are only known from the stake accounts passed in, so whether the
+    // threshold is reached is checked against those rather than taken from reached_threshold.
+    // Failing to read them is left to plan_execution to report.
+    let execute = always_execute || ctx.accounts.transaction.auto_execute;
+    if !execute || !has_quorum(&ctx.accounts.multisig, &ctx.accounts.transaction, now, ctx.remaining_accounts).unwrap_or(true) {
+        return Ok(());
+    }
+    let (Some(multisig_signer), Some(refundee)) = (&ctx.accounts.multisig_signer, &ctx.accounts.refundee) else {
+        require!(!always_execute, ErrorCode::AutoExecuteAccountsMissing);
+        msg!("Not executing: the multisig signer and refundee were not passed");
+        return Ok(());
+    };
+    let plan = match plan_execution(
+        ctx.program_id,
+        &ctx.accounts.multisig,
+        multisig_signer.key,
+        &ctx.accounts.transaction,
+        ctx.accounts.owner.key,
+        ctx.remaining_accounts,
+        Execution::Remaining,
+    ) {
+        Ok(plan) => plan,
+        Err(error) if !always_execute => {
+            msg!("Not executing: {}", error);
+            return Ok(());
+        }
+        Err(error) => return Err(error),
+    };
+    execute_plan(
+        ctx.program_id,
+        &ctx.accounts.multisig,
+        &mut ctx.accounts.transaction,
+        plan,
+        refundee,
+        &ctx.accounts.owner,
+        &mut ctx.accounts.audit_log,
+        &mut ctx.accounts.stats,
+        ctx.remaining_accounts,
+    )
+}
+
+// Executes the instructions of the transaction selected by `execution` if enough owners have
+// signed it and every execute-time guard passes. Once all of its instructions have executed, pays
+// the keeper reward to the executor and closes the account unless retained.
+#[allow(clippy::too_many_arguments)]
+fn execute_approved<'info>(
+    program_id: &Pubkey,
+    multisig: &Account<'info, Multisig>,
+    multisig_signer: &Pubkey,
+    transaction: &mut Account<'info, Transaction>,
+    refundee: &AccountInfo<'info>,
+    executor: &AccountInfo<'info>,
+    audit_log: &mut Option<Account<'info, AuditLog>>,
+    stats: &mut Option<Account<'info, Stats>>,
+    remaining_accounts: &[AccountInfo],
+    execution: Execution,
+) -> Result<()> {
+    let plan = plan_execution(program_id, multisig, multisig_signer, transaction, executor.key, remaining_accounts, execution)?;
+    execute_plan(program_id, multisig, transaction, plan, refundee, executor, audit_log, stats, remaining_accounts)
+}
+
+// The instructions an execution runs, by index and in full, and the signer PDA executing them.
+struct ExecutionPlan {
+    pending: Vec<usize>,
+    instructions: Vec<TransactionInstruction>,
+    signer_key: Pubkey,
+    bump: u8,
 }
 
-#[account]
-pub struct Multisig {
-    pub owners: Vec<Pubkey>,
-    pub threshold: u64,
-    pub nonce: u8,
-    pub owner_set_seqno: u32,
+// Checks every execute-time guard of executing the instructions of the transaction selected by
+// `execution`, without changing anything, returning what the execution would run.
+fn plan_execution(
+    program_id: &Pubkey,
+    multisig: &Account<Multisig>,
+    multisig_signer: &Pubkey,
+    transaction: &Transaction,
+    executor: &Pubkey,
+    remaining_accounts: &[AccountInfo],
+    execution: Execution,
+) -> Result<ExecutionPlan> {
+    require!(!transaction.did_execute, ErrorCode::AlreadyExecuted);
+    assert_not_frozen(multisig, &transaction.instructions)?;
+    let pending: Vec<usize> = match execution {
+        Execution::Index(index) => {
+            let index = index as usize;
+            require!(index < transaction.instructions.len(), ErrorCode::InvalidInstructionIndex);
+            require!(!transaction.executed_mask[index], ErrorCode::AlreadyExecuted);
+            require!(
+                transaction.out_of_order || transaction.executed_mask[..index].iter().all(|executed| *executed),
+                ErrorCode::InstructionOutOfOrder
+            );
+            vec![index]
+        }
+        Execution::Remaining | Execution::WithinLimit => (0..transaction.instructions.len()).filter(|index| !transaction.executed_mask[*index]).collect(),
+    };
+    let instructions: Vec<TransactionInstruction> = pending.iter()
+        .map(|index| transaction.instructions[*index].clone())
+        .collect();
+    require!(Clock::get()?.unix_timestamp < transaction.expires_at, ErrorCode::TransactionExpired);
+    require!(is_eligible_executor(multisig, transaction, executor), ErrorCode::InvalidExecutor);
+    require!(
+        !multisig.strict_separation || is_separate_from_executor(multisig, transaction, executor),
+        ErrorCode::SeparationOfDutiesViolated
+    );
+
+    // Do we have enough signers? A transaction approved by the previous owners, and allowed
+    // to execute under churn protection, already had. Approvals only count once the
+    // transaction is out of quarantine.
+    let now = Clock::get()?.unix_timestamp;
+    require!(now >= transaction.created_at.saturating_add(multisig.min_delay), ErrorCode::TimelockNotElapsed);
+    require!(now >= transaction.created_at.saturating_add(multisig.quarantine_secs), ErrorCode::InQuarantine);
+    if execution == Execution::WithinLimit {
+        require!(approval_count(multisig, transaction, now) > 0, ErrorCode::NotEnoughSigners);
+    } else {
+        require!(has_quorum(multisig, transaction, now, remaining_accounts)?, ErrorCode::NotEnoughSigners);
+    }
+
+    let multisig_key = multisig.key();
+    let (signer_key, bump) = match transaction.purpose {
+        DEFAULT_PURPOSE => (*multisig_signer, multisig.nonce),
+        _ => purpose_signer_address(&multisig_key, transaction.purpose),
+    };
+    require!(transaction.co_approvals.iter().all(|approved| *approved), ErrorCode::CoApprovalMissing);
+
+    // The nonce only has to give a valid signer address at creation, but anything deriving
+    // the multisig signer, clients included, expects the canonical one. A multisig created with
+    // another may only execute a transaction resetting it.
+    require!(
+        transaction.purpose != DEFAULT_PURPOSE
+            || Pubkey::find_program_address(&[multisig_key.as_ref()], program_id).1 == bump
+            || calls_any(&instructions, &[instruction::ResetNonce::DISCRIMINATOR]),
+        ErrorCode::InvalidSignerBump
+    );
+
+    for ix in instructions.iter() {
+        // Only the multisig's own governance instructions may write to the multisig account.
+        require!(
+            ix.program_id == crate::ID || !ix.accounts.iter().any(|acc| acc.pubkey == multisig_key && acc.is_writable),
+            ErrorCode::MultisigAccountProtected
+        );
+        assert_accounts_present(ix, remaining_accounts)?;
+        assert_upgrade_authority(ix, &signer_key, remaining_accounts)?;
+        assert_program_version(multisig, ix, remaining_accounts)?;
+    }
+
+    // The cap applies to the transaction as a whole, however many calls it is executed over.
+    let sol_outflow = |instructions: &[TransactionInstruction]| instructions.iter()
+        .filter_map(|ix| system_transfer_lamports(ix, &signer_key))
+        .try_fold(0u64, |total, lamports| total.checked_add(lamports))
+        .ok_or(ErrorCode::Overflow);
+    require!(sol_outflow(&transaction.instructions)? <= multisig.max_sol_outflow, ErrorCode::OutflowCapExceeded);
+    if multisig.require_signer_rent_exemption {
+        assert_rent_exempt_after(&signer_key, sol_outflow(&instructions)?, remaining_accounts)?;
+    }
+    Ok(ExecutionPlan { pending, instructions, signer_key, bump })
 }
 
-#[account]
-pub struct Transaction {
-    // The multisig account this transaction belongs to.
-    pub multisig: Pubkey,
-    // The instructions to be executed by this transaction
-    pub instructions: Vec<TransactionInstruction>,
-    // signers[index] is true iff multisig.owners[index] signed the transaction.
-    pub signers: Vec<bool>,
-    // Owner set sequence number.
-    pub owner_set_seqno: u32,
+// Executes what plan_execution found the transaction may run. Once all of its instructions have
+// executed, pays the keeper reward to the executor and closes the account unless retained.
+#[allow(clippy::too_many_arguments)]
+fn execute_plan<'info>(
+    program_id: &Pubkey,
+    multisig: &Account<'info, Multisig>,
+    transaction: &mut Account<'info, Transaction>,
+    plan: ExecutionPlan,
+    refundee: &AccountInfo<'info>,
+    executor: &AccountInfo<'info>,
+    audit_log: &mut Option<Account<'info, AuditLog>>,
+    stats: &mut Option<Account<'info, Stats>>,
+    remaining_accounts: &[AccountInfo],
+) -> Result<()> {
+    let ExecutionPlan { pending, instructions, signer_key, bump } = plan;
+    let multisig_key = multisig.key();
+    let purpose = [transaction.purpose];
+
+    // All guards pass, so mark the instructions executed and persist the flags before any CPI. An
+    // instruction which re-enters execute_transaction on this account then can't execute them again.
+    for index in pending.iter() {
+        transaction.executed_mask[*index] = true;
+    }
+    transaction.did_execute = transaction.executed_mask.iter().all(|executed| *executed);
+    if transaction.did_execute {
+        let clock = Clock::get()?;
+        transaction.executed_at = clock.unix_timestamp;
+        transaction.executed_slot = clock.slot;
+    }
+    transaction.exit(program_id)?;
+    // The stats and audit log are reloaded after the CPI, so persist what was recorded before it,
+    // such as the approval which triggered an auto-execution, or it would be lost.
+    if let Some(audit_log) = audit_log {
+        audit_log.exit(program_id)?;
+    }
+    if let Some(stats) = stats {
+        stats.exit(program_id)?;
+    }
+
+    let bump = [bump];
+    let seeds: Vec<&[u8]> = match transaction.purpose {
+        DEFAULT_PURPOSE => vec![multisig_key.as_ref(), &bump],
+        _ => vec![multisig_key.as_ref(), &purpose, &bump],
+    };
+    invoke_instructions(&instructions, &signer_key, &seeds, remaining_accounts)?;
+
+    // The instructions may themselves have recorded entries, so pick those up before appending,
+    // or before writing the accounts back when this execution doesn't complete the transaction.
+    if let Some(audit_log) = audit_log {
+        audit_log.reload()?;
+    }
+    if let Some(stats) = stats {
+        stats.reload()?;
+    }
+    if !transaction.did_execute {
+        return Ok(());
+    }
+    update_stats(stats, |stats| {
+        stats.transactions_executed = stats.transactions_executed.saturating_add(1)
+    });
+    record_audit_entry(audit_log, AuditAction::Execute, executor.key, &transaction.key())?;
+    emit!(TransactionExecuted {
+        multisig: multisig_key,
+        transaction: transaction.key(),
+        executor: executor.key(),
+        owner_set_seqno: transaction.owner_set_seqno,
+        instruction_count: transaction.instructions.len() as u16,
+        executed_at: transaction.executed_at,
+        executed_slot: transaction.executed_slot,
+    });
+
+    let keeper_reward = transaction.keeper_reward;
+    if keeper_reward > 0 {
+        let transaction = transaction.to_account_info();
+        **transaction.try_borrow_mut_lamports()? = transaction.lamports()
+            .checked_sub(keeper_reward)
+            .ok_or(ErrorCode::Overflow)?;
+        **executor.try_borrow_mut_lamports()? = executor.lamports()
+            .checked_add(keeper_reward)
+            .ok_or(ErrorCode::Overflow)?;
+    }
+
+    if !transaction.retain {
+        transaction.close(refundee.clone())?;
+    }
+    Ok(())
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct TransactionInstruction {
-    /// Pubkey of the program that executes this instruction.
-    pub program_id: Pubkey,
-    /// Metadata describing accounts that should be passed to the program.
-    pub accounts: Vec<TransactionAccount>,
-    /// Opaque data passed to the program for its own interpretation.
-    pub data: Vec<u8>,
+// Checks every account the instruction references, and the program it calls, was passed, which
+// the runtime would otherwise only report as a failed invocation.
+fn assert_accounts_present(ix: &TransactionInstruction, accounts: &[AccountInfo]) -> Result<()> {
+    let referenced = ix.accounts.iter().map(|acc| &acc.pubkey).chain(std::iter::once(&ix.program_id));
+    for pubkey in referenced {
+        if !accounts.iter().any(|info| info.key == pubkey) {
+            msg!("Missing remaining account: {}", pubkey);
+            return err!(ErrorCode::MissingRemainingAccount);
+        }
+    }
+    Ok(())
 }
 
-impl From<&TransactionInstruction> for Instruction {
-    fn from(ix: &TransactionInstruction) -> Instruction {
-        Instruction {
-            program_id: ix.program_id,
-            accounts: ix.accounts.iter().map(Into::into).collect(),
-            data: ix.data.clone(),
+// Executes the instructions signed by the multisig PDA `signer_key`, derived from `seeds`.
+fn invoke_instructions(
+    instructions: &[TransactionInstruction],
+    signer_key: &Pubkey,
+    seeds: &[&[u8]],
+    accounts: &[AccountInfo],
+) -> Result<()> {
+    instructions.iter()
+        .map(|ix| {
+            let mut ix: Instruction = ix.into();
+            ix.accounts = ix.accounts.iter()
+                .map(|acc| {
+                    let mut acc = acc.clone();
+                    if acc.pubkey == *signer_key {
+                        acc.is_signer = true;
+                    }
+                    acc
+                })
+                .collect();
+            solana_program::program::invoke_signed(&ix, accounts, &[seeds])
+        })
+        // Collect will process Result objects from the invoke_signed until it finds an error, when it will return that error
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(())
+}
+
+// Approvals resized to `owner_count`, keeping those of owners still at the same index, or none at
+// all when the owner set has changed.
+fn repaired_signers(signers: &[bool], owner_count: usize, owner_set_matches: bool) -> Vec<bool> {
+    let mut repaired = vec![false; owner_count];
+    if owner_set_matches {
+        for (repaired, signed) in repaired.iter_mut().zip(signers) {
+            *repaired = *signed;
         }
     }
+    repaired
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct TransactionAccount {
-    pub pubkey: Pubkey,
-    pub is_signer: bool,
-    pub is_writable: bool,
+// Applies `update` to the stats account, if one was passed.
+fn update_stats(stats: &mut Option<Account<Stats>>, update: impl FnOnce(&mut Stats)) {
+    if let Some(stats) = stats {
+        update(stats);
+    }
 }
 
-impl From<&TransactionAccount> for AccountMeta {
-    fn from(account: &TransactionAccount) -> AccountMeta {
-        match account.is_writable {
-            false => AccountMeta::new_readonly(account.pubkey, account.is_signer),
-            true => AccountMeta::new(account.pubkey, account.is_signer),
+// The sequence number of the owner set following the one numbered `seqno`.
+fn next_owner_set_seqno(seqno: u32) -> Result<u32> {
+    Ok(seqno.checked_add(1).ok_or(ErrorCode::Overflow)?)
+}
+
+// Appends the current owner set to the owner history, evicting the oldest entry when full.
+fn record_owner_set(multisig: &mut Multisig, changed_at: i64) {
+    if multisig.owner_history.len() == OWNER_HISTORY_CAPACITY {
+        multisig.owner_history.remove(0);
+    }
+    multisig.owner_history.push(OwnerSetRecord {
+        owner_set_seqno: multisig.owner_set_seqno,
+        owners_hash: multisig.owners_hash,
+        changed_at,
+    });
+}
+
+// Appends an entry to the audit log, if one was passed, evicting the oldest entry when full.
+fn record_audit_entry(
+    audit_log: &mut Option<Account<AuditLog>>,
+    action: AuditAction,
+    actor: &Pubkey,
+    transaction: &Pubkey,
+) -> Result<()> {
+    if let Some(audit_log) = audit_log {
+        if audit_log.entries.len() == AUDIT_LOG_CAPACITY {
+            audit_log.entries.remove(0);
         }
+        audit_log.entries.push(AuditEntry {
+            action,
+            actor: *actor,
+            transaction: *transaction,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
     }
+    Ok(())
 }
 
-fn assert_unique_owners(owners: &[Pubkey]) -> Result<()> {
-    for (i, owner) in owners.iter().enumerate() {
+// SHA-256 of the serialized instructions.
+fn hash_instructions(instructions: &[TransactionInstruction]) -> Result<[u8; 32]> {
+    Ok(solana_program::hash::hash(&instructions.try_to_vec()?).to_bytes())
+}
+
+// SHA-256 of the concatenated owner pubkeys, in order.
+pub fn hash_owners(owners: &[Pubkey]) -> [u8; 32] {
+    let owners: Vec<&[u8]> = owners.iter().map(|owner| owner.as_ref()).collect();
+    solana_program::hash::hashv(&owners).to_bytes()
+}
+
+// Populates a freshly created multisig account with the default configuration.
+fn init_multisig(
+    multisig: &mut Multisig,
+    multisig_signer: &Pubkey,
+    owners: Vec<Pubkey>,
+    threshold: u64,
+    nonce: u8,
+    min_delay: i64,
+    weights: Option<Vec<u64>>,
+) -> Result<()> {
+    validate_owners(&owners, multisig_signer)?;
+    let weights = weights.unwrap_or_else(|| vec![1; owners.len()]);
+    assert_valid_weights(&weights, owners.len())?;
+    assert_valid_threshold(threshold, total_weight(&weights))?;
+    require!(!owners.is_empty(), ErrorCode::NotEnoughOwners);
+
+    multisig.owners_hash = hash_owners(&owners);
+    multisig.owner_expiries = vec![None; owners.len()];
+    multisig.owner_added_at = vec![0; owners.len()];
+    multisig.owner_roles = vec![ROLE_ALL; owners.len()];
+    multisig.no_single_owner_majority = false;
+    multisig.owners = owners;
+    multisig.threshold = threshold;
+    multisig.nonce = nonce;
+    multisig.owner_set_seqno = 0;
+    multisig.max_sol_outflow = u64::MAX;
+    multisig.max_pending_secs = i64::MAX;
+    multisig.default_expiry_secs = i64::MAX;
+    multisig.owner_change_cooldown = 0;
+    multisig.last_owner_change_at = 0;
+    multisig.new_owner_delay = 0;
+    multisig.churn_grace_secs = 0;
+    multisig.executors = Vec::new();
+    multisig.require_distinct_payer_proposer = false;
+    multisig.require_signer_rent_exemption = false;
+    multisig.threshold_against_active = false;
+    multisig.snapshot_quorum = false;
+    multisig.require_memo = false;
+    multisig.reject_duplicate_approvals = false;
+    multisig.pinned_programs = Vec::new();
+    multisig.stake_accounts = Vec::new();
+    multisig.stake_threshold = 0;
+    multisig.admin_stake_threshold = 0;
+    multisig.weakening_delay = 0;
+    multisig.pending_threshold = None;
+    multisig.pending_threshold_ready_at = 0;
+    multisig.pending_weakening_delay = None;
+    multisig.pending_weakening_delay_ready_at = 0;
+    multisig.max_open_owner_changes = 0;
+    multisig.open_owner_changes = 0;
+    multisig.public_execution = false;
+    multisig.quarantine_secs = 0;
+    multisig.min_delay = min_delay;
+    multisig.strict_separation = false;
+    multisig.weights = weights;
+    multisig.owner_history = Vec::new();
+    multisig.allowed_programs = Vec::new();
+    multisig.read_threshold = 0;
+    multisig.cancel_approval_limit = 0;
+    multisig.admin_threshold = 0;
+    multisig.daily_limit = 0;
+    multisig.spent_today = 0;
+    multisig.last_reset = 0;
+    multisig.max_instructions = 0;
+    multisig.max_data_len = 0;
+    multisig.pending_owners = Vec::new();
+    multisig.pending_owner_accepted = Vec::new();
+    multisig.pending_owners_expire_at = 0;
+    multisig.frozen = false;
+    multisig.cancel_threshold = 1;
+    multisig.transaction_count = 0;
+    record_owner_set(multisig, Clock::get()?.unix_timestamp);
+    Ok(())
+}
+
+// Checks the instructions of a prospective transaction against every policy of the multisig which
+// depends on them alone.
+fn assert_valid_instructions(multisig: &Multisig, instructions: &[TransactionInstruction]) -> Result<()> {
+    require!(!instructions.is_empty(), ErrorCode::MissingInstructions);
+    assert_not_frozen(multisig, instructions)?;
+    require!(instructions.len() <= max_safe_instructions(multisig.owners.len()), ErrorCode::TransactionTooLarge);
+    require!(
+        multisig.max_instructions == 0 || instructions.len() <= multisig.max_instructions as usize,
+        ErrorCode::TooManyInstructions
+    );
+    require!(
+        multisig.max_data_len == 0 || instructions.iter().all(|ix| ix.data.len() <= multisig.max_data_len as usize),
+        ErrorCode::InstructionDataTooLarge
+    );
+    assert_instruction_account_counts(instructions)?;
+    assert_consistent_account_metas(instructions)?;
+    require!(
+        multisig.allowed_programs.is_empty()
+            || instructions.iter().all(|ix| multisig.allowed_programs.contains(&ix.program_id)),
+        ErrorCode::ProgramNotAllowed
+    );
+    require!(
+        !is_owner_change(instructions)
+            || multisig.max_open_owner_changes == 0
+            || multisig.open_owner_changes < multisig.max_open_owner_changes,
+        ErrorCode::OwnerChangeInProgress
+    );
+    Ok(())
+}
+
+// Populates a freshly created transaction account, automatically approved by the proposer.
+#[allow(clippy::too_many_arguments)]
+fn init_transaction(
+    tx: &mut Transaction,
+    multisig: &mut Account<Multisig>,
+    proposer: &Pubkey,
+    payer: &Pubkey,
+    instructions: Vec<TransactionInstruction>,
+    purpose: u8,
+    expires_at: Option<i64>,
+    memo: String,
+) -> Result<()> {
+    assert_valid_instructions(multisig, &instructions)?;
+    require!(!multisig.require_memo || !memo.trim().is_empty(), ErrorCode::MemoRequired);
+    require!(memo.len() <= MAX_MEMO_LEN, ErrorCode::MemoTooLong);
+    require!(
+        !multisig.require_distinct_payer_proposer || payer != proposer,
+        ErrorCode::PayerMustDiffer
+    );
+
+    let created_at = Clock::get()?.unix_timestamp;
+    let latest_expiry = created_at.saturating_add(multisig.max_pending_secs);
+    let expires_at = match expires_at {
+        None | Some(0) => created_at.saturating_add(multisig.default_expiry_secs).min(latest_expiry),
+        Some(expires_at) => expires_at,
+    };
+    require!(expires_at <= latest_expiry, ErrorCode::ExpiryTooLong);
+
+    let owner_index = multisig
+        .owners
+        .iter()
+        .position(|a| a == proposer)
+        .ok_or(ErrorCode::InvalidOwner)?;
+    require!(is_owner_active(multisig, owner_index, created_at), ErrorCode::OwnerExpired);
+    require!(!is_owner_cooling_off(multisig, owner_index, created_at), ErrorCode::NewOwnerCoolingOff);
+    let required_role = required_role(&instructions);
+    require!(multisig.owner_roles[owner_index] & required_role == required_role, ErrorCode::InsufficientRole);
+
+    let mut signers = Vec::new();
+    signers.resize(multisig.owners.len(), false);
+    signers[owner_index] = true;
+
+    if is_owner_change(&instructions) {
+        multisig.open_owner_changes = multisig.open_owner_changes.saturating_add(1);
+    }
+    tx.seq = multisig.transaction_count;
+    multisig.transaction_count = multisig.transaction_count.checked_add(1).ok_or(ErrorCode::Overflow)?;
+
+    tx.executed_mask = vec![false; instructions.len()];
+    tx.cancel_signers = vec![false; signers.len()];
+    tx.instructions = instructions;
+    tx.signers = signers;
+    tx.multisig = multisig.key();
+    tx.owner_set_seqno = multisig.owner_set_seqno;
+    tx.purpose = purpose;
+    tx.created_at = created_at;
+    tx.expires_at = expires_at;
+    tx.proposer = *proposer;
+    tx.memo = memo;
+    tx.required_at_creation = 0;
+    if multisig.threshold_against_active && multisig.snapshot_quorum {
+        tx.required_at_creation = required_approvals(multisig, tx, created_at);
+    }
+    tx.reached_threshold = has_reached_threshold(multisig, tx, created_at, &[]);
+
+    Ok(())
+}
+
+// Counts and logs a transaction just set up by init_transaction, and announces it.
+fn record_transaction_created(
+    multisig: &Pubkey,
+    transaction: &Account<Transaction>,
+    proposer: &Pubkey,
+    stats: &mut Option<Account<Stats>>,
+    audit_log: &mut Option<Account<AuditLog>>,
+) -> Result<()> {
+    update_stats(stats, |stats| {
+        stats.transactions_created = stats.transactions_created.saturating_add(1)
+    });
+    record_audit_entry(audit_log, AuditAction::Create, proposer, &transaction.key())?;
+    emit!(TransactionCreated {
+        multisig: *multisig,
+        transaction: transaction.key(),
+        proposer: *proposer,
+        owner_set_seqno: transaction.owner_set_seqno,
+        seq: transaction.seq,
+    });
+    Ok(())
+}
+
+// Whether any instruction of the transaction has been executed, or is executing.
+fn has_started_execution(transaction: &Transaction) -> bool {
+    transaction.did_execute || transaction.executed_mask.iter().any(|executed| *executed)
+}
+
+// The role bits a proposer needs for every instruction's category.
+fn required_role(instructions: &[TransactionInstruction]) -> u8 {
+    instructions.iter()
+        .map(|ix| {
+            if ix.program_id == solana_program::system_program::ID
+                || ix.program_id == spl_token::ID
+                || ix.program_id == spl_token_2022::ID {
+                ROLE_TRANSFER
+            } else if ix.program_id == crate::ID {
+                ROLE_GOVERNANCE
+            } else {
+                ROLE_OTHER
+            }
+        })
+        .fold(0, |role, category| role | category)
+}
+
+// Whether any of the instructions changes the owners of a multisig of this program.
+fn is_owner_change(instructions: &[TransactionInstruction]) -> bool {
+    calls_any(instructions, &[
+        instruction::SetOwners::DISCRIMINATOR,
+        instruction::AddOwners::DISCRIMINATOR,
+        instruction::RemoveOwner::DISCRIMINATOR,
+        instruction::SetOwnersAndChangeThreshold::DISCRIMINATOR,
+        instruction::RestoreProfile::DISCRIMINATOR,
+        instruction::ReorderOwners::DISCRIMINATOR,
+        instruction::ReplaceOwner::DISCRIMINATOR,
+        instruction::ProposeOwners::DISCRIMINATOR,
+    ])
+}
+
+// Whether any of the instructions changes the owners, their weights or the thresholds of a
+// multisig of this program, how the threshold is counted or lowered, or otherwise weakens or
+// removes the multisig's controls.
+fn is_admin_change(instructions: &[TransactionInstruction]) -> bool {
+    is_owner_change(instructions)
+        || calls_any(instructions, &[
+            instruction::ChangeThreshold::DISCRIMINATOR,
+            instruction::SetOwnerWeights::DISCRIMINATOR,
+            instruction::ConfigureOwner::DISCRIMINATOR,
+            instruction::SetAdminThreshold::DISCRIMINATOR,
+            instruction::SetStakeWeighting::DISCRIMINATOR,
+            instruction::SetOwnerExpiry::DISCRIMINATOR,
+            instruction::SetThresholdAgainstActive::DISCRIMINATOR,
+            instruction::SetSnapshotQuorum::DISCRIMINATOR,
+            instruction::SetWeakeningDelay::DISCRIMINATOR,
+            instruction::SetReadThreshold::DISCRIMINATOR,
+            instruction::SetCancelThreshold::DISCRIMINATOR,
+            instruction::RegisterBreakGlass::DISCRIMINATOR,
+            instruction::CloseMultisig::DISCRIMINATOR,
+        ])
+}
+
+// Checks the multisig isn't frozen, unless the instructions only unfreeze it.
+fn assert_not_frozen(multisig: &Multisig, instructions: &[TransactionInstruction]) -> Result<()> {
+    require!(!multisig.frozen || is_unfreeze(instructions), ErrorCode::MultisigFrozen);
+    Ok(())
+}
+
+// Whether the instructions do nothing but unfreeze a multisig of this program.
+fn is_unfreeze(instructions: &[TransactionInstruction]) -> bool {
+    instructions.iter().all(|ix| {
+        ix.program_id == crate::ID && ix.data.len() >= 8 && ix.data[..8] == instruction::Unfreeze::DISCRIMINATOR[..]
+    })
+}
+
+// Whether any of the instructions calls one of this program's instructions with the given
+// discriminators.
+fn calls_any(instructions: &[TransactionInstruction], discriminators: &[[u8; 8]]) -> bool {
+    instructions.iter().any(|ix| {
+        ix.program_id == crate::ID && ix.data.len() >= 8 && discriminators.iter().any(|d| ix.data[..8] == d[..])
+    })
+}
+
+// Checks a cancel threshold is at least one owner and at most all of them.
+fn assert_valid_cancel_threshold(cancel_threshold: u64, owner_count: usize) -> Result<()> {
+    require!(cancel_threshold > 0 && cancel_threshold <= owner_count as u64, ErrorCode::InvalidThreshold);
+    Ok(())
+}
+
+// Checks an admin threshold is either unset or at least the threshold.
+fn assert_valid_admin_threshold(admin_threshold: u64, threshold: u64) -> Result<()> {
+    require!(admin_threshold == 0 || admin_threshold >= threshold, ErrorCode::InvalidAdminThreshold);
+    Ok(())
+}
+
+// Checks the admin stake threshold is at least the stake threshold and, as admin_threshold isn't
+// applied under stake weighting, set whenever admin_threshold is.
+fn assert_valid_admin_stake_threshold(multisig: &Multisig) -> Result<()> {
+    require!(
+        multisig.stake_accounts.is_empty()
+            || ((multisig.admin_stake_threshold == 0 || multisig.admin_stake_threshold >= multisig.stake_threshold)
+                && (multisig.admin_threshold == 0 || multisig.admin_stake_threshold > 0)),
+        ErrorCode::InvalidAdminStakeThreshold
+    );
+    Ok(())
+}
+
+// Stops counting a proposal changing the owners as open once it is cancelled. Proposals from
+// previous owner sets were already dropped from the count when the owners changed.
+fn release_owner_change(multisig: &mut Multisig, transaction: &Transaction) {
+    if transaction.owner_set_seqno == multisig.owner_set_seqno && is_owner_change(&transaction.instructions) {
+        multisig.open_owner_changes = multisig.open_owner_changes.saturating_sub(1);
+    }
+}
+
+// How many typically sized instructions fit in the largest transaction account a multisig with
+// `owner_count` owners can create, which is also the most instructions a transaction may have.
+pub fn max_safe_instructions(owner_count: usize) -> usize {
+    let no_instructions: [TransactionInstruction; 0] = [];
+    MAX_TRANSACTION_DATA_LEN.saturating_sub(transaction_data_len!(no_instructions, owner_count, 0, 0, 0)) / AVERAGE_INSTRUCTION_LEN
+}
+
+// Checks no instruction references more accounts than the runtime allows a cross-program
+// invocation, which would otherwise only fail on execution.
+fn assert_instruction_account_counts(instructions: &[TransactionInstruction]) -> Result<()> {
+    require!(
+        instructions.iter().all(|ix| ix.accounts.len() <= solana_program::syscalls::MAX_CPI_INSTRUCTION_ACCOUNTS as usize),
+        ErrorCode::TooManyAccountsInInstruction
+    );
+    Ok(())
+}
+
+// Checks no instruction lists the same account twice with different signer or writable flags, which
+// the runtime would otherwise merge on invocation. Listing it twice with the same flags is fine.
+fn assert_consistent_account_metas(instructions: &[TransactionInstruction]) -> Result<()> {
+    for ix in instructions {
+        let mut accounts: Vec<&TransactionAccount> = ix.accounts.iter().collect();
+        accounts.sort_unstable_by_key(|acc| acc.pubkey);
         require!(
-            !owners.iter().skip(i + 1).any(|item| item == owner),
-            ErrorCode::UniqueOwners
-        )
+            accounts.windows(2).all(|pair| {
+                pair[0].pubkey != pair[1].pubkey
+                    || (pair[0].is_signer == pair[1].is_signer && pair[0].is_writable == pair[1].is_writable)
+            }),
+            ErrorCode::DuplicateAccountMeta
+        );
+    }
+    Ok(())
+}
+
+// Checks `display_order` is either empty or a permutation of the indices of `instruction_count` instructions.
+fn assert_display_order(display_order: &[u16], instruction_count: usize) -> Result<()> {
+    if display_order.is_empty() {
+        return Ok(());
+    }
+    let display_order: Vec<usize> = display_order.iter().map(|index| *index as usize).collect();
+    require!(is_permutation(&display_order, instruction_count), ErrorCode::InvalidDisplayOrder);
+    Ok(())
+}
+
+// Whether `indices` lists each of 0..len exactly once.
+fn is_permutation(indices: &[usize], len: usize) -> bool {
+    let mut sorted = indices.to_vec();
+    sorted.sort_unstable();
+    sorted.into_iter().eq(0..len)
+}
+
+// `items` rearranged so the item at `order[index]` moves to `index`.
+fn permuted<T: Clone>(items: &[T], order: &[usize]) -> Vec<T> {
+    order.iter().map(|index| items[*index].clone()).collect()
+}
+
+// The loader instruction moving the upgrade authority of `program` from `current_authority` to `new_authority`.
+fn set_upgrade_authority_instruction(
+    program: &Pubkey,
+    current_authority: &Pubkey,
+    new_authority: &Pubkey,
+) -> TransactionInstruction {
+    from_instruction(&bpf_loader_upgradeable::set_upgrade_authority(program, current_authority, Some(new_authority)))
+}
+
+// An SPL token transfer of `amount` tokens from `source` to `destination`, authorised by `authority`.
+fn token_transfer_instruction(
+    token_program: &Pubkey,
+    source: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+) -> TransactionInstruction {
+    const TRANSFER: u8 = 3;
+    from_instruction(&Instruction {
+        program_id: *token_program,
+        accounts: vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: [&[TRANSFER], amount.to_le_bytes().as_slice()].concat(),
+    })
+}
+
+// For loader instructions changing a program's upgrade authority, checks the program data account
+// (when passed in) records `signer` as the current authority, so a mis-targeted handoff fails with a
+// clear error rather than inside the loader.
+fn assert_upgrade_authority(ix: &TransactionInstruction, signer: &Pubkey, accounts: &[AccountInfo]) -> Result<()> {
+    const SET_AUTHORITY: u32 = 4;
+    const SET_AUTHORITY_CHECKED: u32 = 7;
+    if ix.program_id != bpf_loader_upgradeable::ID
+        || ix.data.len() < 4
+        || ![SET_AUTHORITY, SET_AUTHORITY_CHECKED].contains(&u32::from_le_bytes(ix.data[0..4].try_into().unwrap())) {
+        return Ok(());
+    }
+    let Some(program_data) = ix.accounts.first()
+        .and_then(|acc| accounts.iter().find(|info| *info.key == acc.pubkey)) else {
+        return Ok(());
+    };
+
+    // ProgramData state: enum tag (u32), slot (u64), then Option<Pubkey> upgrade authority.
+    let data = program_data.try_borrow_data()?;
+    let is_current_authority = *program_data.owner == bpf_loader_upgradeable::ID
+        && data.len() >= UpgradeableLoaderState::size_of_programdata_metadata()
+        && data[12] == 1
+        && data[13..45] == signer.as_ref()[..];
+    require!(is_current_authority, ErrorCode::NotCurrentUpgradeAuthority);
+    Ok(())
+}
+
+// For instructions calling a pinned program, checks the program data account (which must be passed
+// in) holds code hashing to the pinned value, i.e. the program hasn't been upgraded since it was pinned.
+fn assert_program_version(multisig: &Multisig, ix: &TransactionInstruction, accounts: &[AccountInfo]) -> Result<()> {
+    let Some(pin) = multisig.pinned_programs.iter().find(|pin| pin.program_id == ix.program_id) else {
+        return Ok(());
+    };
+    let (program_data_address, _) = Pubkey::find_program_address(&[pin.program_id.as_ref()], &bpf_loader_upgradeable::ID);
+    let program_data = accounts.iter()
+        .find(|info| *info.key == program_data_address)
+        .ok_or(ErrorCode::ProgramVersionMismatch)?;
+
+    let data = program_data.try_borrow_data()?;
+    let metadata_len = UpgradeableLoaderState::size_of_programdata_metadata();
+    let is_pinned_version = *program_data.owner == bpf_loader_upgradeable::ID
+        && data.len() >= metadata_len
+        && solana_program::hash::hash(&data[metadata_len..]).to_bytes() == pin.program_data_hash;
+    require!(is_pinned_version, ErrorCode::ProgramVersionMismatch);
+    Ok(())
+}
+
+// Checks every instruction expects the same signers, other than the multisig's own `signer`.
+fn assert_consistent_signers(instructions: &[TransactionInstruction], signer: &Pubkey) -> Result<()> {
+    let external_signers = |ix: &TransactionInstruction| {
+        let mut signers: Vec<Pubkey> = ix.accounts.iter()
+            .filter(|acc| acc.is_signer && acc.pubkey != *signer)
+            .map(|acc| acc.pubkey)
+            .collect();
+        signers.sort();
+        signers.dedup();
+        signers
+    };
+    let expected = instructions.first().map(external_signers).unwrap_or_default();
+    require!(
+        instructions.iter().all(|ix| external_signers(ix) == expected),
+        ErrorCode::InconsistentSigners
+    );
+    Ok(())
+}
+
+// Checks the `signer` account, if passed in, stays rent exempt after transferring out `outflow` lamports.
+fn assert_rent_exempt_after(signer: &Pubkey, outflow: u64, accounts: &[AccountInfo]) -> Result<()> {
+    let Some(signer) = accounts.iter().find(|info| info.key == signer) else {
+        return Ok(());
+    };
+    let remaining = signer.lamports().checked_sub(outflow);
+    let minimum = Rent::get()?.minimum_balance(signer.data_len());
+    require!(remaining.is_some_and(|remaining| remaining >= minimum), ErrorCode::WouldBreakRentExemption);
+    Ok(())
+}
+
+// The lamports moved out of `from` if the instruction is a system program transfer from it.
+fn system_transfer_lamports(ix: &TransactionInstruction, from: &Pubkey) -> Option<u64> {
+    const TRANSFER: u32 = 2;
+    if ix.program_id != solana_program::system_program::ID
+        || ix.accounts.first().map(|acc| &acc.pubkey) != Some(from)
+        || ix.data.len() < 12
+        || u32::from_le_bytes(ix.data[0..4].try_into().unwrap()) != TRANSFER {
+        return None;
     }
+    Some(u64::from_le_bytes(ix.data[4..12].try_into().unwrap()))
+}
+
+// Rejects duplicate owners and addresses that can never sign as an owner: the zero (system
+// program) address, this program and the multisig's own signer PDA.
+fn validate_owners(owners: &[Pubkey], multisig_signer: &Pubkey) -> Result<()> {
+    require!(owners.len() <= MAX_OWNERS, ErrorCode::TooManyOwners);
+    assert_unique_owners(owners)?;
+    require!(
+        !owners.iter().any(|owner| *owner == Pubkey::default() || *owner == crate::ID || owner == multisig_signer),
+        ErrorCode::InvalidOwnerKey
+    );
+    Ok(())
+}
+
+// Sorts a copy of the owners so any duplicates end up next to each other.
+fn assert_unique_owners(owners: &[Pubkey]) -> Result<()> {
+    let mut sorted = owners.to_vec();
+    sorted.sort_unstable();
+    require!(sorted.windows(2).all(|pair| pair[0] != pair[1]), ErrorCode::UniqueOwners);
     Ok(())
 }
 
-fn execute_set_owners(multisig: &mut Account<Multisig>, owners: Vec<Pubkey>) -> Result<()> {
-    assert_unique_owners(&owners)?;
+// Replaces the owners, and the threshold if given, recording and announcing the new owner set.
+// Owners carried over keep what is recorded for them, wherever they now are in the list.
+fn execute_set_owners(multisig: &mut Account<Multisig>, owners: Vec<Pubkey>, threshold: Option<u64>) -> Result<()> {
+    let carried_from = owners.iter()
+        .map(|owner| multisig.owners.iter().position(|a| a == owner))
+        .collect();
+    execute_set_owners_carrying(multisig, owners, carried_from, threshold)
+}
+
+// As execute_set_owners, the owner at each index taking over what is recorded for the previous
+// owner at `carried_from[index]`, or starting afresh for `None`.
+fn execute_set_owners_carrying(
+    multisig: &mut Account<Multisig>,
+    owners: Vec<Pubkey>,
+    carried_from: Vec<Option<usize>>,
+    threshold: Option<u64>,
+) -> Result<()> {
+    validate_owners(&owners, &transaction_signer_address(multisig, DEFAULT_PURPOSE)?)?;
     require!(!owners.is_empty(), ErrorCode::NotEnoughOwners);
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now >= multisig.last_owner_change_at.saturating_add(multisig.owner_change_cooldown),
+        ErrorCode::OwnerChangeCooldown
+    );
     // Increasing the number of owners requires reallocation of space in the data account.
     // This requires a signer to pay the fees for more space, but the instruction will be executed by the multisig.
     require!(multisig_data_len!(owners.len()) <= multisig.to_account_info().data.borrow().len(), ErrorCode::TooManyOwners);
 
-    if (owners.len() as u64) < multisig.threshold {
-        multisig.threshold = owners.len() as u64;
+    let weights: Vec<u64> = carried_from.iter()
+        .map(|from| from.and_then(|index| multisig.weights.get(index).copied()).unwrap_or(1))
+        .collect();
+    if total_weight(&weights) < multisig.threshold {
+        multisig.threshold = total_weight(&weights);
     }
+    multisig.cancel_threshold = multisig.cancel_threshold.min(owners.len() as u64);
 
-    multisig.owners = owners;
-    multisig.owner_set_seqno += 1;
+    // Owners carried over keep their expiry and when they were added. New owners are added now.
+    let owner_expiries = carried_from.iter()
+        .map(|from| from.and_then(|index| multisig.owner_expiries.get(index).copied().flatten()))
+        .collect();
+    let owner_added_at = carried_from.iter()
+        .map(|from| from.and_then(|index| multisig.owner_added_at.get(index).copied()).unwrap_or(now))
+        .collect();
+    // And their role, new owners being able to propose anything.
+    let owner_roles = carried_from.iter()
+        .map(|from| from.and_then(|index| multisig.owner_roles.get(index).copied()).unwrap_or(ROLE_ALL))
+        .collect();
+
+    // Likewise their stake account, new owners having none until one is registered.
+    if !multisig.stake_accounts.is_empty() {
+        multisig.stake_accounts = carried_from.iter()
+            .map(|from| from.and_then(|index| multisig.stake_accounts.get(index).copied()).unwrap_or_default())
+            .collect();
+    }
 
+    multisig.owners_hash = hash_owners(&owners);
+    multisig.owner_expiries = owner_expiries;
+    multisig.owner_added_at = owner_added_at;
+    multisig.owner_roles = owner_roles;
+    multisig.weights = weights;
+    // Any change of owners supersedes a proposed one.
+    multisig.pending_owners = Vec::new();
+    multisig.pending_owner_accepted = Vec::new();
+    multisig.pending_owners_expire_at = 0;
+    let previous_owners = std::mem::replace(&mut multisig.owners, owners);
+    multisig.owner_set_seqno = next_owner_set_seqno(multisig.owner_set_seqno)?;
+    multisig.open_owner_changes = 0;
+    multisig.last_owner_change_at = now;
+    record_owner_set(multisig, now);
+    if let Some(threshold) = threshold {
+        execute_change_threshold(multisig, threshold)?;
+    }
+    assert_no_single_owner_majority(multisig)?;
+
+    emit!(OwnersChanged {
+        multisig: multisig.key(),
+        previous_owners,
+        owners: multisig.owners.clone(),
+        threshold: multisig.threshold,
+        owner_set_seqno: multisig.owner_set_seqno,
+    });
+    Ok(())
+}
+
+fn assert_valid_threshold(threshold: u64, total_weight: u64) -> Result<()> {
+    require!(threshold > 0 && threshold <= total_weight, ErrorCode::InvalidThreshold);
+    Ok(())
+}
+
+fn assert_valid_weights(weights: &[u64], owner_count: usize) -> Result<()> {
+    require!(weights.len() == owner_count && weights.iter().all(|weight| *weight > 0), ErrorCode::InvalidWeights);
     Ok(())
 }
 
+// Checks no single owner's weight meets the threshold on its own, if the multisig asks for that.
+fn assert_no_single_owner_majority(multisig: &Multisig) -> Result<()> {
+    require!(
+        !multisig.no_single_owner_majority || multisig.weights.iter().all(|weight| *weight < multisig.threshold),
+        ErrorCode::SingleOwnerMajority
+    );
+    Ok(())
+}
+
+fn total_weight(weights: &[u64]) -> u64 {
+    weights.iter().fold(0u64, |total, weight| total.saturating_add(*weight))
+}
+
+// The weights of `owners` once they replace the current owners: owners carried over keep
+// theirs, new owners count once.
+fn remapped_weights(multisig: &Multisig, owners: &[Pubkey]) -> Vec<u64> {
+    owners.iter()
+        .map(|owner| {
+            multisig.owners.iter()
+                .position(|a| a == owner)
+                .and_then(|index| multisig.weights.get(index).copied())
+                .unwrap_or(1)
+        })
+        .collect()
+}
+
 fn execute_change_threshold(multisig: &mut Multisig, threshold: u64) -> Result<()> {
-    require!(threshold > 0 && threshold <= multisig.owners.len() as u64, ErrorCode::InvalidThreshold);
+    assert_valid_threshold(threshold, total_weight(&multisig.weights))?;
+    // Raising the threshold only makes the multisig harder to use, lowering it easier, so the
+    // latter waits out the weakening delay.
+    if threshold < multisig.threshold && multisig.weakening_delay > 0 {
+        multisig.pending_threshold = Some(threshold);
+        multisig.pending_threshold_ready_at = Clock::get()?.unix_timestamp.saturating_add(multisig.weakening_delay);
+        return Ok(());
+    }
     multisig.threshold = threshold;
-    Ok(())
+    multisig.pending_threshold = None;
+    assert_no_single_owner_majority(multisig)
 }
 
 #[error_code]
@@ -431,4 +3786,300 @@ pub enum ErrorCode {
     AccountCloseFailed,
     #[msg("The number of instructions must be greater than zero.")]
     MissingInstructions,
+    #[msg("The transaction transfers more SOL than the multisig allows per transaction.")]
+    OutflowCapExceeded,
+    #[msg("The multisig signer is not the current upgrade authority of the program.")]
+    NotCurrentUpgradeAuthority,
+    #[msg("Transaction expiry is beyond the maximum pending time of the multisig.")]
+    ExpiryTooLong,
+    #[msg("Transaction has expired.")]
+    TransactionExpired,
+    #[msg("Maximum pending time must be positive.")]
+    InvalidMaxPendingSecs,
+    #[msg("Instructions expect different external signers.")]
+    InconsistentSigners,
+    #[msg("Default expiry must be positive.")]
+    InvalidDefaultExpirySecs,
+    #[msg("Owners were changed too recently.")]
+    OwnerChangeCooldown,
+    #[msg("The number of executors exceeds the maximum.")]
+    TooManyExecutors,
+    #[msg("The payer must differ from the proposer.")]
+    PayerMustDiffer,
+    #[msg("The owner's signing rights have expired.")]
+    OwnerExpired,
+    #[msg("Break-glass delay must be positive.")]
+    InvalidBreakGlassDelay,
+    #[msg("Break-glass has not been armed.")]
+    BreakGlassNotArmed,
+    #[msg("Break-glass delay has not elapsed.")]
+    BreakGlassDelayNotElapsed,
+    #[msg("Instructions do not match the break-glass template.")]
+    BreakGlassMismatch,
+    #[msg("The transaction would leave its signer below rent-exemption.")]
+    WouldBreakRentExemption,
+    #[msg("The transaction does not belong to this multisig.")]
+    InvalidTransaction,
+    #[msg("The owner key cannot sign as an owner.")]
+    InvalidOwnerKey,
+    #[msg("Only the proposer of the transaction may do this.")]
+    NotProposer,
+    #[msg("A memo is required.")]
+    MemoRequired,
+    #[msg("The owner has already approved this transaction.")]
+    AlreadyApproved,
+    #[msg("The owner was added too recently to approve.")]
+    NewOwnerCoolingOff,
+    #[msg("The display order must list each instruction index exactly once.")]
+    InvalidDisplayOrder,
+    #[msg("Too many pinned programs.")]
+    TooManyPinnedPrograms,
+    #[msg("A called program is not at its pinned version.")]
+    ProgramVersionMismatch,
+    #[msg("Stake weighting needs one stake account per owner and a positive stake threshold.")]
+    InvalidStakeWeighting,
+    #[msg("A stake account is missing or not a token account.")]
+    InvalidStakeAccount,
+    #[msg("The new owner order must list each owner index exactly once.")]
+    InvalidOwnerOrder,
+    #[msg("The multisig nonce is not the canonical bump of its signer.")]
+    InvalidSignerBump,
+    #[msg("The transaction has more instructions than can safely fit in its account.")]
+    TransactionTooLarge,
+    #[msg("There is no pending threshold change.")]
+    NoPendingThresholdChange,
+    #[msg("The weakening delay has not elapsed.")]
+    WeakeningDelayNotElapsed,
+    #[msg("Not every co-multisig has approved this transaction.")]
+    CoApprovalMissing,
+    #[msg("The multisig is not a co-multisig of this transaction.")]
+    NotACoMultisig,
+    #[msg("An instruction references more accounts than can be passed to a program.")]
+    TooManyAccountsInInstruction,
+    #[msg("Another proposal changing the owners is already open.")]
+    OwnerChangeInProgress,
+    #[msg("The transaction is still in quarantine.")]
+    InQuarantine,
+    #[msg("The multisig config is inconsistent.")]
+    InvalidConfig,
+    #[msg("The executor may not also be the proposer or the only approver.")]
+    SeparationOfDutiesViolated,
+    #[msg("There must be a positive weight for each owner.")]
+    InvalidWeights,
+    #[msg("The multisig signer and refundee must be passed to execute a transaction on approval.")]
+    AutoExecuteAccountsMissing,
+    #[msg("The transaction has no instruction at the given index.")]
+    InvalidInstructionIndex,
+    #[msg("Transactions of this multisig may not call the given program.")]
+    ProgramNotAllowed,
+    #[msg("Too many allowed programs.")]
+    TooManyAllowedPrograms,
+    #[msg("The memo is longer than the maximum allowed.")]
+    MemoTooLong,
+    #[msg("Only instructions of this program may write to the multisig account.")]
+    MultisigAccountProtected,
+    #[msg("The admin threshold must be at least the threshold.")]
+    InvalidAdminThreshold,
+    #[msg("Only a single SOL transfer from the multisig signer may be executed within the daily limit.")]
+    NotASingleTransfer,
+    #[msg("The transfer exceeds what is left of the daily limit.")]
+    DailyLimitExceeded,
+    #[msg("The transaction has more instructions than the multisig allows.")]
+    TooManyInstructions,
+    #[msg("An instruction has more data than the multisig allows.")]
+    InstructionDataTooLarge,
+    #[msg("The signer is not one of the proposed owners.")]
+    NotAPendingOwner,
+    #[msg("The multisig is frozen, so may only act on a transaction unfreezing it.")]
+    MultisigFrozen,
+    #[msg("Not enough owners have approved cancelling the transaction.")]
+    NotEnoughCancelApprovals,
+    #[msg("An account referenced by the transaction's instructions was not passed.")]
+    MissingRemainingAccount,
+    #[msg("The source must be a token account of the given token program held by the multisig signer.")]
+    InvalidTokenSource,
+    #[msg("The nonce is not the canonical bump of the multisig signer.")]
+    InvalidNonce,
+    #[msg("An instruction lists the same account more than once with different flags.")]
+    DuplicateAccountMeta,
+    #[msg("The proposer's role doesn't cover every instruction of the transaction.")]
+    InsufficientRole,
+    #[msg("The proposed owners have expired.")]
+    PendingOwnersExpired,
+    #[msg("Instructions before this one have not been executed yet.")]
+    InstructionOutOfOrder,
+    #[msg("There is no pending weakening delay change.")]
+    NoPendingWeakeningDelayChange,
+    #[msg("The minimum delay after creating the transaction has not elapsed.")]
+    TimelockNotElapsed,
+    #[msg("The break-glass template may not change the owners, threshold or other admin settings.")]
+    BreakGlassAdminChange,
+    #[msg("The admin stake threshold must be at least the stake threshold, and set whenever the admin threshold is.")]
+    InvalidAdminStakeThreshold,
+    #[msg("A single owner's weight may not meet the threshold on its own.")]
+    SingleOwnerMajority,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account_metas(accounts: Vec<TransactionAccount>) -> Vec<TransactionInstruction> {
+        vec![TransactionInstruction { program_id: Pubkey::new_unique(), accounts, data: vec![] }]
+    }
+
+    #[test]
+    fn assert_consistent_account_metas_accepts_distinct_accounts() {
+        let instructions = account_metas(vec![
+            TransactionAccount { pubkey: Pubkey::new_unique(), is_signer: true, is_writable: true },
+            TransactionAccount { pubkey: Pubkey::new_unique(), is_signer: false, is_writable: false },
+        ]);
+        assert!(assert_consistent_account_metas(&instructions).is_ok());
+    }
+
+    #[test]
+    fn assert_consistent_account_metas_accepts_repeated_accounts_with_the_same_flags() {
+        let account = TransactionAccount { pubkey: Pubkey::new_unique(), is_signer: false, is_writable: true };
+        let instructions = account_metas(vec![account.clone(), account]);
+        assert!(assert_consistent_account_metas(&instructions).is_ok());
+    }
+
+    #[test]
+    fn assert_consistent_account_metas_rejects_repeated_accounts_with_different_flags() {
+        let pubkey = Pubkey::new_unique();
+        let instructions = account_metas(vec![
+            TransactionAccount { pubkey, is_signer: false, is_writable: false },
+            TransactionAccount { pubkey: Pubkey::new_unique(), is_signer: false, is_writable: false },
+            TransactionAccount { pubkey, is_signer: false, is_writable: true },
+        ]);
+        assert_eq!(assert_consistent_account_metas(&instructions).unwrap_err(), ErrorCode::DuplicateAccountMeta.into());
+    }
+
+    fn calling(program_ids: &[Pubkey]) -> Vec<TransactionInstruction> {
+        program_ids.iter()
+            .map(|program_id| TransactionInstruction { program_id: *program_id, accounts: vec![], data: vec![] })
+            .collect()
+    }
+
+    #[test]
+    fn required_role_covers_the_category_of_every_instruction() {
+        assert_eq!(required_role(&calling(&[solana_program::system_program::ID, spl_token::ID])), ROLE_TRANSFER);
+        assert_eq!(required_role(&calling(&[crate::ID])), ROLE_GOVERNANCE);
+        assert_eq!(
+            required_role(&calling(&[spl_token_2022::ID, crate::ID, Pubkey::new_unique()])),
+            ROLE_TRANSFER | ROLE_GOVERNANCE | ROLE_OTHER
+        );
+    }
+
+    #[test]
+    fn assert_unique_owners_accepts_distinct_owners() {
+        let owners: Vec<Pubkey> = (0..50).map(|_| Pubkey::new_unique()).collect();
+        assert!(assert_unique_owners(&owners).is_ok());
+    }
+
+    #[test]
+    fn assert_unique_owners_rejects_duplicates_anywhere() {
+        let mut owners: Vec<Pubkey> = (0..50).map(|_| Pubkey::new_unique()).collect();
+        owners[49] = owners[0];
+        assert_eq!(assert_unique_owners(&owners).unwrap_err(), ErrorCode::UniqueOwners.into());
+    }
+
+    #[test]
+    fn multisig_with_max_owners_can_be_created() {
+        const { assert!(multisig_data_len!(MAX_OWNERS) <= MAX_TRANSACTION_DATA_LEN) };
+    }
+
+    #[test]
+    fn from_instruction_round_trips() {
+        let ix = Instruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![
+                AccountMeta::new(Pubkey::new_unique(), true),
+                AccountMeta::new(Pubkey::new_unique(), false),
+                AccountMeta::new_readonly(Pubkey::new_unique(), true),
+                AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            ],
+            data: vec![1, 2, 3],
+        };
+
+        assert_eq!(Instruction::from(&from_instruction(&ix)), ix);
+    }
+
+    #[test]
+    fn next_owner_set_seqno_increments() {
+        assert_eq!(next_owner_set_seqno(0).unwrap(), 1);
+        assert_eq!(next_owner_set_seqno(u32::MAX - 1).unwrap(), u32::MAX);
+    }
+
+    #[test]
+    fn next_owner_set_seqno_overflows_at_max() {
+        assert_eq!(next_owner_set_seqno(u32::MAX).unwrap_err(), error!(ErrorCode::Overflow));
+    }
+
+    #[test]
+    fn is_same_day_splits_days_at_midnight_utc() {
+        assert!(is_same_day(0, SECONDS_PER_DAY - 1));
+        assert!(!is_same_day(SECONDS_PER_DAY - 1, SECONDS_PER_DAY));
+        assert!(is_same_day(10 * SECONDS_PER_DAY + 5, 10 * SECONDS_PER_DAY + 7));
+        assert!(!is_same_day(-1, 0));
+    }
+
+    #[test]
+    fn repaired_signers_extends_short_signers() {
+        assert_eq!(repaired_signers(&[true, false], 3, true), vec![true, false, false]);
+    }
+
+    #[test]
+    fn repaired_signers_truncates_long_signers() {
+        assert_eq!(repaired_signers(&[true, false, true, true], 3, true), vec![true, false, true]);
+    }
+
+    #[test]
+    fn repaired_signers_clears_signers_of_changed_owner_set() {
+        assert_eq!(repaired_signers(&[true, true], 3, false), vec![false, false, false]);
+    }
+
+    #[test]
+    fn max_safe_instructions_fit_in_a_transaction_account() {
+        assert_eq!(max_safe_instructions(3), 41);
+        let instructions = vec![
+            TransactionInstruction {
+                program_id: Pubkey::new_unique(),
+                accounts: vec![TransactionAccount { pubkey: Pubkey::new_unique(), is_signer: false, is_writable: false }; 4],
+                data: vec![0; 64],
+            };
+            max_safe_instructions(3)
+        ];
+        assert!(transaction_data_len!(instructions, 3, 0, 0, 0) <= MAX_TRANSACTION_DATA_LEN);
+    }
+
+    #[test]
+    fn max_safe_instructions_shrinks_with_more_owners() {
+        assert!(max_safe_instructions(300) < max_safe_instructions(3));
+        assert_eq!(max_safe_instructions(MAX_TRANSACTION_DATA_LEN), 0);
+    }
+
+    #[test]
+    fn instruction_account_counts_are_capped_at_the_cpi_limit() {
+        let instruction_with_accounts = |count: usize| TransactionInstruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![TransactionAccount { pubkey: Pubkey::new_unique(), is_signer: false, is_writable: false }; count],
+            data: vec![],
+        };
+        assert!(assert_instruction_account_counts(&[instruction_with_accounts(255)]).is_ok());
+        assert!(assert_instruction_account_counts(&[instruction_with_accounts(1), instruction_with_accounts(256)]).is_err());
+    }
+
+    #[test]
+    fn permuted_moves_each_item_to_its_new_index() {
+        assert!(is_permutation(&[2, 0, 1], 3));
+        assert_eq!(permuted(&['a', 'b', 'c'], &[2, 0, 1]), vec!['c', 'a', 'b']);
+    }
+
+    #[test]
+    fn is_permutation_rejects_repeated_and_missing_indices() {
+        assert!(!is_permutation(&[0, 0, 1], 3));
+        assert!(!is_permutation(&[0, 1], 3));
+        assert!(!is_permutation(&[0, 1, 3], 3));
+    }
 }
@@ -0,0 +1,112 @@
+import assert from "assert";
+import {setUpValidator} from "./utils/before";
+import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
+import {PublicKey, SystemProgram} from "@solana/web3.js";
+import {MultisigAccount, MultisigDsl} from "./utils/multisigDsl";
+import {describe} from "mocha";
+import {fail} from "node:assert";
+
+describe("Test allowed programs", async () => {
+  let provider: AnchorProvider;
+  let program: Program;
+  let dsl: MultisigDsl;
+
+  before(async () => {
+    let result = await setUpValidator(false);
+    program = result.program;
+    provider = result.provider;
+    dsl = new MultisigDsl(program, provider);
+  });
+
+  async function setAllowedPrograms(multisig: MultisigAccount, programs: Array<PublicKey>) {
+    let setAllowedProgramsInstruction = await program.methods
+      .setAllowedPrograms(programs)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setAllowedProgramsInstruction]);
+  }
+
+  it("should create and execute a transaction calling an allowed program", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    await setAllowedPrograms(multisig, [SystemProgram.programId, program.programId]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.allowedPrograms, [SystemProgram.programId, program.programId]);
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(30000);
+
+  it("should not create a transaction calling a program which isn't allowed", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    await setAllowedPrograms(multisig, [program.programId]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    try {
+      await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+      fail("Should have failed to propose transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: ProgramNotAllowed. Error Number: 6055. Error Message: Transactions of this multisig may not call the given program."));
+    }
+  }).timeout(30000);
+
+  it("should validate a proposal against the allowed programs without creating it", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    await setAllowedPrograms(multisig, [program.programId]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    try {
+      await dsl.validateProposal(multisig.address, [transactionInstruction]);
+      fail("Should have failed to validate proposal");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: ProgramNotAllowed. Error Number: 6055. Error Message: Transactions of this multisig may not call the given program."));
+    }
+
+    let allowedInstruction = await program.methods
+      .exportConfig()
+      .accounts({
+        multisig: multisig.address,
+      })
+      .instruction();
+    await dsl.validateProposal(multisig.address, [allowedInstruction]);
+  }).timeout(30000);
+
+  it("should allow any program when no programs are allowed explicitly", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    await setAllowedPrograms(multisig, []);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(30000);
+});
@@ -137,6 +137,62 @@ describe("Test performing signing and execution", async () => {
     await dsl.assertBalance(multisig.signer, 0);
   }).timeout(10000);
 
+  it("should give a receipt of an owner's approval", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    const receipt = await dsl.approvalReceipt(multisig.address, transactionAddress, ownerB.publicKey);
+    assert.ok(receipt.multisig.equals(multisig.address));
+    assert.ok(receipt.transaction.equals(transactionAddress));
+    assert.ok(receipt.owner.equals(ownerB.publicKey));
+    assert.strictEqual(receipt.ownerSetSeqno, 0);
+    assert.strictEqual(receipt.approved, true);
+
+    const nonApproval = await dsl.approvalReceipt(multisig.address, transactionAddress, ownerC.publicKey);
+    assert.strictEqual(nonApproval.approved, false);
+  }).timeout(20000);
+
+  it("should reject a repeated approval when configured", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let setRejectDuplicateApprovalsInstruction = await program.methods
+      .setRejectDuplicateApprovals(true)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setRejectDuplicateApprovalsInstruction]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+      fail("Should have failed to approve transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: AlreadyApproved. Error Number: 6033. Error Message: The owner has already approved this transaction."));
+    }
+
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+    await dsl.assertBalance(multisig.signer, 0);
+  }).timeout(20000);
+
   it("should not execute transaction if same user has approved multiple times to reach the threshold", async () => {
     const multisig = await dsl.createMultisig(2, 3, 1_000_000);
     const [ownerA, ownerB, _ownerC] = multisig.owners;
@@ -165,6 +221,192 @@ describe("Test performing signing and execution", async () => {
     await dsl.assertBalance(multisig.signer, 1_000_000);
   });
 
+  it("should not execute transaction after an approval is revoked", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await dsl.revokeApproval(ownerB, multisig.address, transactionAddress);
+
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.signers, [true, false, false]);
+
+    try {
+      await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NotEnoughSigners. Error Number: 6003. Error Message: Not enough owners signed this transaction"));
+    }
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+  }).timeout(20000);
+
+  it("should not count the proposer's approval when the proposer does not approve", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address, undefined, {proposerApproves: false});
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.signers, [false, false, false]);
+
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    try {
+      await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NotEnoughSigners. Error Number: 6003. Error Message: Not enough owners signed this transaction"));
+    }
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+  }).timeout(20000);
+
+  it("should not allow non owner to revoke", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    try {
+      await dsl.revokeApproval(Keypair.generate(), multisig.address, transactionAddress);
+      fail("Should have failed to revoke approval");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidOwner. Error Number: 6000. Error Message: The given owner is not part of this multisig"));
+    }
+  });
+
+  it("should apply the read threshold only to transactions which write to no account", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+
+    let setReadThresholdInstruction = await program.methods
+      .setReadThreshold(new BN(1))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setReadThresholdInstruction]);
+
+    let readOnlyInstruction = await program.methods
+      .exportConfig()
+      .accounts({
+        multisig: multisig.address,
+      })
+      .instruction();
+    const readOnlyAddress: PublicKey = await dsl.proposeTransaction(ownerA, [readOnlyInstruction], multisig.address);
+    await dsl.executeTransactionWithMultipleInstructions(readOnlyAddress, [readOnlyInstruction], multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+    assert.strictEqual(await provider.connection.getAccountInfo(readOnlyAddress, "confirmed"), null);
+
+    let writingInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: provider.publicKey,
+    });
+    const writingAddress: PublicKey = await dsl.proposeTransaction(ownerA, [writingInstruction], multisig.address);
+    try {
+      await dsl.executeTransaction(writingAddress, writingInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NotEnoughSigners. Error Number: 6003. Error Message: Not enough owners signed this transaction"));
+    }
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+  }).timeout(30000);
+
+  it("should let a single owner with enough weight meet the threshold alone", async () => {
+    const [founder, ownerB, ownerC] = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const multisig = await dsl.createMultisigWithOwners(2, [founder, ownerB, ownerC], 1_000_000, 0, [2, 1, 1]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    // ownerB alone doesn't weigh enough
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerB, [transactionInstruction], multisig.address);
+    try {
+      await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerB.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NotEnoughSigners. Error Number: 6003. Error Message: Not enough owners signed this transaction"));
+    }
+
+    // The founder counts twice
+    const founderTransactionAddress: PublicKey = await dsl.proposeTransaction(founder, [transactionInstruction], multisig.address);
+    await dsl.executeTransaction(founderTransactionAddress, transactionInstruction, multisig.signer, multisig.address, founder, founder.publicKey);
+
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(20000);
+
+  it("should reject weights letting a single owner meet the threshold alone when configured", async () => {
+    const [founder, ownerB, ownerC] = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const multisig = await dsl.createMultisigWithOwners(5, [founder, ownerB, ownerC], 1_000_000, 0, [5, 1, 1]);
+
+    let setNoSingleOwnerMajorityInstruction = await program.methods
+      .setNoSingleOwnerMajority(true)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    try {
+      await dsl.executeWithQuorum(multisig, [setNoSingleOwnerMajorityInstruction]);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: SingleOwnerMajority. Error Number: 6078. Error Message: A single owner's weight may not meet the threshold on its own."));
+    }
+
+    let changeThresholdInstruction = await program.methods
+      .changeThreshold(new BN(6))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [changeThresholdInstruction, setNoSingleOwnerMajorityInstruction]);
+
+    let setOwnerWeightsInstruction = await program.methods
+      .setOwnerWeights([new BN(6), new BN(1), new BN(1)])
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    try {
+      await dsl.executeWithQuorum(multisig, [setOwnerWeightsInstruction]);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: SingleOwnerMajority. Error Number: 6078. Error Message: A single owner's weight may not meet the threshold on its own."));
+    }
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.strictEqual(actualMultisig.noSingleOwnerMajority, true);
+    assert.deepStrictEqual(actualMultisig.weights.map(weight => weight.toNumber()), [5, 1, 1]);
+  }).timeout(30000);
+
   it("should not allow non owner to approve", async () => {
     const multisig = await dsl.createMultisig(2, 3, 1_000_000);
     const [ownerA, _ownerB, _ownerC] = multisig.owners;
@@ -228,4 +470,135 @@ describe("Test performing signing and execution", async () => {
     await dsl.assertBalance(multisig1.signer, 950_000);
     await dsl.assertBalance(multisig2.signer, 1_000_000);
   }).timeout(20000);
+
+  it("should list the approval of each owner", async () => {
+    const multisig = await dsl.createMultisig(3, 3, 1_000_000);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    const roster = await dsl.approvalRoster(multisig.address, transactionAddress);
+    assert.deepStrictEqual(
+      roster.map(entry => [entry.owner.toBase58(), entry.approved]),
+      [[ownerA.publicKey.toBase58(), true], [ownerB.publicKey.toBase58(), true], [ownerC.publicKey.toBase58(), false]]
+    );
+  }).timeout(20000);
+
+  it("should approve several transactions at once", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    const instructions = [0, 1, 2].map(() => SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    }));
+    const transactionAddresses: Array<PublicKey> = [];
+    for (const instruction of instructions) {
+      transactionAddresses.push(await dsl.proposeTransaction(ownerA, [instruction], multisig.address));
+    }
+    // Approving one beforehand doesn't stop the others being approved.
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddresses[1]);
+
+    await dsl.approveManyTransactions(ownerB, multisig.address, transactionAddresses);
+
+    for (const transactionAddress of transactionAddresses) {
+      let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+      assert.deepStrictEqual(transactionAccount.signers, [true, true, false]);
+    }
+    for (let i = 0; i < instructions.length; i++) {
+      await dsl.executeTransaction(transactionAddresses[i], instructions[i], multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+    }
+    await dsl.assertBalance(multisig.signer, 700_000);
+  }).timeout(30000);
+
+  it("should not approve a transaction of another multisig among several", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const otherMultisig = await dsl.createMultisigWithOwners(2, multisig.owners, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    const transactionAddress = await dsl.proposeTransaction(ownerA, [SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    })], multisig.address);
+    const otherTransactionAddress = await dsl.proposeTransaction(ownerA, [SystemProgram.transfer({
+      fromPubkey: otherMultisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    })], otherMultisig.address);
+
+    try {
+      await dsl.approveManyTransactions(ownerB, multisig.address, [transactionAddress, otherTransactionAddress]);
+      fail("Should have failed to approve transactions");
+    } catch (e) {
+      assert.match(e.message, new RegExp(".*Error Code: InvalidTransaction."));
+    }
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.signers, [true, false, false]);
+  }).timeout(30000);
+
+  it("should not approve a retained transaction once executed", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.markRetain(ownerA, transactionAddress);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+
+    try {
+      await dsl.approveManyTransactions(ownerC, multisig.address, [transactionAddress]);
+      fail("Should have failed to approve transactions");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: AlreadyExecuted. Error Number: 6007. Error Message: The given transaction has already been executed"));
+    }
+    try {
+      await dsl.approveTransaction(ownerC, multisig.address, transactionAddress);
+      fail("Should have failed to approve transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: AlreadyExecuted. Error Number: 6007. Error Message: The given transaction has already been executed"));
+    }
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.signers, [true, true, false]);
+  }).timeout(30000);
+
+  it("should not revoke an approval of a retained transaction once executed", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.markRetain(ownerA, transactionAddress);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+
+    try {
+      await dsl.revokeApproval(ownerB, multisig.address, transactionAddress);
+      fail("Should have failed to revoke approval");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: AlreadyExecuted. Error Number: 6007. Error Message: The given transaction has already been executed"));
+    }
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.signers, [true, true, false]);
+  }).timeout(30000);
 });
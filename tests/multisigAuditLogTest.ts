@@ -0,0 +1,146 @@
+import assert from "assert";
+import {setUpValidator} from "./utils/before";
+import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
+import {PublicKey, SystemProgram} from "@solana/web3.js";
+import {MultisigDsl} from "./utils/multisigDsl";
+import {describe} from "mocha";
+
+describe("Test audit log", async () => {
+  let provider: AnchorProvider;
+  let program: Program;
+  let dsl: MultisigDsl;
+
+  before(async () => {
+    let result = await setUpValidator(false);
+    program = result.program;
+    provider = result.provider;
+    dsl = new MultisigDsl(program, provider);
+  });
+
+  it("should record the transaction lifecycle in order", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+    const auditLog = await dsl.createAuditLog(multisig.address);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const executedAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, executedAddress);
+    await dsl.executeTransaction(executedAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+
+    const cancelledAddress: PublicKey = await dsl.proposeTransaction(ownerC, [transactionInstruction], multisig.address);
+    await dsl.cancelTransaction(cancelledAddress, multisig.address, ownerA, ownerC.publicKey);
+
+    let auditLogAccount = await program.account.auditLog.fetch(auditLog);
+    assert.ok(auditLogAccount.multisig.equals(multisig.address));
+    assert.deepStrictEqual(
+      auditLogAccount.entries.map(entry => [Object.keys(entry.action)[0], entry.actor.toBase58(), entry.transaction.toBase58()]),
+      [
+        ["create", ownerA.publicKey.toBase58(), executedAddress.toBase58()],
+        ["approve", ownerB.publicKey.toBase58(), executedAddress.toBase58()],
+        ["execute", ownerB.publicKey.toBase58(), executedAddress.toBase58()],
+        ["create", ownerC.publicKey.toBase58(), cancelledAddress.toBase58()],
+        ["cancel", ownerA.publicKey.toBase58(), cancelledAddress.toBase58()],
+      ]
+    );
+    for (let i = 1; i < auditLogAccount.entries.length; i++) {
+      assert.ok(auditLogAccount.entries[i - 1].timestamp.lte(auditLogAccount.entries[i].timestamp));
+    }
+  }).timeout(20000);
+
+  it("should record a deleted transaction as cancelled by its proposer", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    const auditLog = await dsl.createAuditLog(multisig.address);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.deleteTransaction(transactionAddress, multisig.address, ownerA, ownerA.publicKey);
+
+    let auditLogAccount = await program.account.auditLog.fetch(auditLog);
+    assert.deepStrictEqual(
+      auditLogAccount.entries.map(entry => [Object.keys(entry.action)[0], entry.actor.toBase58(), entry.transaction.toBase58()]),
+      [
+        ["create", ownerA.publicKey.toBase58(), transactionAddress.toBase58()],
+        ["cancel", ownerA.publicKey.toBase58(), transactionAddress.toBase58()],
+      ]
+    );
+  }).timeout(20000);
+
+  it("should record both the approval and the execution of an auto-executing transaction", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const auditLog = await dsl.createAuditLog(multisig.address);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.markAutoExecute(ownerA, transactionAddress);
+    await dsl.approveAndExecuteTransaction(ownerB, multisig.address, multisig.signer, transactionAddress, [transactionInstruction], ownerA.publicKey);
+
+    let auditLogAccount = await program.account.auditLog.fetch(auditLog);
+    assert.deepStrictEqual(
+      auditLogAccount.entries.map(entry => [Object.keys(entry.action)[0], entry.actor.toBase58(), entry.transaction.toBase58()]),
+      [
+        ["create", ownerA.publicKey.toBase58(), transactionAddress.toBase58()],
+        ["approve", ownerB.publicKey.toBase58(), transactionAddress.toBase58()],
+        ["execute", ownerB.publicKey.toBase58(), transactionAddress.toBase58()],
+      ]
+    );
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(20000);
+
+  it("should record both the approval and the execution of approve_and_execute", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const auditLog = await dsl.createAuditLog(multisig.address);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveAndExecute(ownerB, multisig.address, multisig.signer, transactionAddress, [transactionInstruction], ownerA.publicKey);
+
+    let auditLogAccount = await program.account.auditLog.fetch(auditLog);
+    assert.deepStrictEqual(
+      auditLogAccount.entries.map(entry => [Object.keys(entry.action)[0], entry.actor.toBase58(), entry.transaction.toBase58()]),
+      [
+        ["create", ownerA.publicKey.toBase58(), transactionAddress.toBase58()],
+        ["approve", ownerB.publicKey.toBase58(), transactionAddress.toBase58()],
+        ["execute", ownerB.publicKey.toBase58(), transactionAddress.toBase58()],
+      ]
+    );
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(20000);
+
+  it("should not require an audit log", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    await dsl.executeWithQuorum(multisig, [transactionInstruction]);
+
+    assert.strictEqual(await provider.connection.getAccountInfo(dsl.auditLogAddress(multisig.address)), null);
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(20000);
+});
@@ -0,0 +1,162 @@
+import assert from "assert";
+import {setUpValidator} from "./utils/before";
+import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
+import {Keypair, SystemProgram} from "@solana/web3.js";
+import {MultisigDsl} from "./utils/multisigDsl";
+import {describe} from "mocha";
+import {fail} from "node:assert";
+
+describe("Test break-glass execution", async () => {
+  let provider: AnchorProvider;
+  let program: Program;
+  let dsl: MultisigDsl;
+
+  before(async () => {
+    let result = await setUpValidator(false);
+    program = result.program;
+    provider = result.provider;
+    dsl = new MultisigDsl(program, provider);
+  });
+
+  it("should only execute the break-glass template once its delay has elapsed", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [_ownerA, _ownerB, ownerC] = multisig.owners;
+    const coldWallet = Keypair.generate().publicKey;
+
+    let evacuate = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: coldWallet,
+    });
+    await dsl.registerBreakGlass(multisig, [evacuate], ownerC.publicKey, 5);
+    await dsl.armBreakGlass(ownerC, multisig);
+
+    try {
+      await dsl.emergencyExecute(ownerC, multisig, [evacuate]);
+      fail("Should have failed to execute break-glass");
+    } catch (e) {
+      assert.ok(e.message.includes("Break-glass delay has not elapsed."));
+    }
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+
+    await new Promise((resolve) => setTimeout(resolve, 8000));
+    await dsl.emergencyExecute(ownerC, multisig, [evacuate]);
+
+    await dsl.assertBalance(multisig.signer, 0);
+    await dsl.assertBalance(coldWallet, 1_000_000);
+    let breakGlass = await program.account.breakGlass.fetch(dsl.breakGlassAddress(multisig.address));
+    assert.ok(new BN(0).eq(breakGlass.armedAt), "Should have disarmed break-glass");
+  }).timeout(40000);
+
+  it("should not execute instructions other than the break-glass template", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [_ownerA, _ownerB, ownerC] = multisig.owners;
+
+    let evacuate = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: Keypair.generate().publicKey,
+    });
+    await dsl.registerBreakGlass(multisig, [evacuate], ownerC.publicKey, 1);
+    await dsl.armBreakGlass(ownerC, multisig);
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+
+    let drain = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: ownerC.publicKey,
+    });
+    try {
+      await dsl.emergencyExecute(ownerC, multisig, [drain]);
+      fail("Should have failed to execute break-glass");
+    } catch (e) {
+      assert.ok(e.message.includes("Instructions do not match the break-glass template."));
+    }
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+  }).timeout(40000);
+
+  it("should replace the break-glass template once the registered one is closed", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [_ownerA, ownerB, ownerC] = multisig.owners;
+
+    let evacuate = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: Keypair.generate().publicKey,
+    });
+    await dsl.registerBreakGlass(multisig, [evacuate], ownerC.publicKey, 5);
+    await dsl.armBreakGlass(ownerC, multisig);
+
+    await dsl.closeBreakGlass(multisig);
+    assert.strictEqual(await provider.connection.getAccountInfo(dsl.breakGlassAddress(multisig.address), "confirmed"), null);
+
+    await dsl.registerBreakGlass(multisig, [evacuate], ownerB.publicKey, 10);
+    let breakGlass = await program.account.breakGlass.fetch(dsl.breakGlassAddress(multisig.address));
+    assert.ok(breakGlass.executor.equals(ownerB.publicKey));
+    assert.ok(new BN(10).eq(breakGlass.delaySecs));
+    assert.ok(new BN(0).eq(breakGlass.armedAt), "Should not carry over the arming of the closed template");
+  }).timeout(40000);
+
+  it("should not execute the break-glass template while frozen", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, ownerC] = multisig.owners;
+
+    let evacuate = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: Keypair.generate().publicKey,
+    });
+    await dsl.registerBreakGlass(multisig, [evacuate], ownerC.publicKey, 1);
+    await dsl.armBreakGlass(ownerC, multisig);
+    await dsl.freeze(multisig.address, ownerA);
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+
+    try {
+      await dsl.emergencyExecute(ownerC, multisig, [evacuate]);
+      fail("Should have failed to execute break-glass");
+    } catch (e) {
+      assert.ok(e.message.includes("The multisig is frozen, so may only act on a transaction unfreezing it."));
+    }
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+  }).timeout(40000);
+
+  it("should not register a break-glass template making admin changes", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [_ownerA, _ownerB, ownerC] = multisig.owners;
+
+    let setOwnersInstruction = await program.methods
+      .setOwners([ownerC.publicKey])
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    try {
+      await dsl.registerBreakGlass(multisig, [setOwnersInstruction], ownerC.publicKey, 5);
+      fail("Should have failed to register break-glass");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: BreakGlassAdminChange. Error Number: 6076. Error Message: The break-glass template may not change the owners, threshold or other admin settings."));
+    }
+    assert.strictEqual(await provider.connection.getAccountInfo(dsl.breakGlassAddress(multisig.address)), null);
+  }).timeout(20000);
+
+  it("should only let the designated owner arm break-glass", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, ownerC] = multisig.owners;
+
+    let evacuate = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: Keypair.generate().publicKey,
+    });
+    await dsl.registerBreakGlass(multisig, [evacuate], ownerC.publicKey, 5);
+
+    try {
+      await dsl.armBreakGlass(ownerA, multisig);
+      fail("Should have failed to arm break-glass");
+    } catch (e) {
+      assert.match(e.message, new RegExp(".*Error Code: InvalidExecutor. Error Number: 6010."));
+    }
+  }).timeout(20000);
+});
@@ -35,7 +35,7 @@ describe("Test transaction cancellation", async () => {
 
     await dsl.cancelTransaction(transactionAddress, multisig.address, ownerB, ownerA.publicKey);
 
-    await dsl.assertBalance(ownerA.publicKey, 2_108_880); // this is the rent exemption amount
+    await dsl.assertBalance(ownerA.publicKey, 3_187_680); // this is the rent exemption amount
 
     let transactionActInfo = await provider.connection.getAccountInfo(
       transactionAddress,
@@ -72,7 +72,7 @@ describe("Test transaction cancellation", async () => {
     // Now cancel the original transaction instruction (the corresponding TX account owner set will be outdated at this point)
     await dsl.assertBalance(ownerB.publicKey, 0);
     await dsl.cancelTransaction(transactionAddress, multisig.address, ownerB, ownerB.publicKey);
-    await dsl.assertBalance(ownerB.publicKey, 2_108_880); // this is the rent exemption amount
+    await dsl.assertBalance(ownerB.publicKey, 3_187_680); // this is the rent exemption amount
 
     let transactionActInfo = await provider.connection.getAccountInfo(
       transactionAddress,
@@ -81,6 +81,227 @@ describe("Test transaction cancellation", async () => {
     assert.strictEqual(transactionActInfo, null);
   }).timeout(20000);
 
+  it("should only let the proposer cancel a transaction with as many approvals as the cancel approval limit", async () => {
+    const multisig = await dsl.createMultisig(3, 3);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+
+    let setCancelApprovalLimitInstruction = await program.methods
+      .setCancelApprovalLimit(2)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setCancelApprovalLimitInstruction]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.cancelTransaction(transactionAddress, multisig.address, ownerC, ownerA.publicKey);
+      fail("Should have failed to cancel transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: TransactionAlreadySigned. Error Number: 6004. Error Message: Cannot delete a transaction that has been signed by an owner."));
+    }
+
+    await dsl.cancelTransaction(transactionAddress, multisig.address, ownerA, ownerA.publicKey);
+    assert.strictEqual(await provider.connection.getAccountInfo(transactionAddress, "confirmed"), null);
+  }).timeout(30000);
+
+  it("should let any owner cancel a transaction with fewer approvals than the cancel approval limit", async () => {
+    const multisig = await dsl.createMultisig(3, 3);
+    const [ownerA, _ownerB, ownerC] = multisig.owners;
+
+    let setCancelApprovalLimitInstruction = await program.methods
+      .setCancelApprovalLimit(2)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setCancelApprovalLimitInstruction]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    await dsl.cancelTransaction(transactionAddress, multisig.address, ownerC, ownerA.publicKey);
+    assert.strictEqual(await provider.connection.getAccountInfo(transactionAddress, "confirmed"), null);
+  }).timeout(30000);
+
+  it("should cancel a transaction once enough owners approve cancelling it", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+
+    let setCancelThresholdInstruction = await program.methods
+      .setCancelThreshold(new BN(2))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setCancelThresholdInstruction]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    await dsl.approveCancel(transactionAddress, multisig.address, ownerB);
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.cancelSigners, [false, true, false]);
+
+    await dsl.cancelTransaction(transactionAddress, multisig.address, ownerC, ownerA.publicKey);
+    assert.strictEqual(await provider.connection.getAccountInfo(transactionAddress, "confirmed"), null);
+  }).timeout(30000);
+
+  it("should not cancel a transaction with fewer owners approving than the cancel threshold", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let setCancelThresholdInstruction = await program.methods
+      .setCancelThreshold(new BN(2))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setCancelThresholdInstruction]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    // Approving cancellation and cancelling is still only one owner.
+    await dsl.approveCancel(transactionAddress, multisig.address, ownerB);
+    try {
+      await dsl.cancelTransaction(transactionAddress, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to cancel transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NotEnoughCancelApprovals. Error Number: 6066. Error Message: Not enough owners have approved cancelling the transaction."));
+    }
+    assert.notStrictEqual(await provider.connection.getAccountInfo(transactionAddress, "confirmed"), null);
+  }).timeout(30000);
+
+  it("should not allow a cancel threshold of no owners or more owners than there are", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+
+    for (const cancelThreshold of [0, 4]) {
+      let setCancelThresholdInstruction = await program.methods
+        .setCancelThreshold(new BN(cancelThreshold))
+        .accounts({
+          multisig: multisig.address,
+          multisigSigner: multisig.signer,
+        })
+        .instruction();
+      try {
+        await dsl.executeWithQuorum(multisig, [setCancelThresholdInstruction]);
+        fail("Should have failed to execute transaction");
+      } catch (e) {
+        assert.match(e.message,
+          new RegExp(".*Error Code: InvalidThreshold. Error Number: 6008. Error Message: Threshold must be less than or equal to the number of owners and greater than zero."));
+      }
+    }
+  }).timeout(30000);
+
+  it("should lower the cancel threshold when removing an owner leaves fewer owners than it", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [_ownerA, _ownerB, ownerC] = multisig.owners;
+
+    let setCancelThresholdInstruction = await program.methods
+      .setCancelThreshold(new BN(3))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    let removeOwnerInstruction = await program.methods
+      .removeOwner(ownerC.publicKey)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setCancelThresholdInstruction, removeOwnerInstruction]);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.strictEqual(actualMultisig.owners.length, 2);
+    assert.strictEqual(actualMultisig.cancelThreshold.toNumber(), 2);
+  }).timeout(30000);
+
+  it("should let the proposer delete a transaction nobody else approved", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    await dsl.deleteTransaction(transactionAddress, multisig.address, ownerA, ownerA.publicKey);
+
+    await dsl.assertBalance(ownerA.publicKey, 3_187_680); // this is the rent exemption amount
+    assert.strictEqual(await provider.connection.getAccountInfo(transactionAddress, "confirmed"), null);
+  }).timeout(20000);
+
+  it("should not let an owner other than the proposer delete a transaction", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    try {
+      await dsl.deleteTransaction(transactionAddress, multisig.address, ownerB, ownerB.publicKey);
+      fail("Should have failed to delete transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: UnableToDelete. Error Number: 6006. Error Message: Cannot delete a transaction the owner did not create."));
+    }
+  }).timeout(20000);
+
+  it("should not let the proposer delete a transaction another owner approved", async () => {
+    const multisig = await dsl.createMultisig(3, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.deleteTransaction(transactionAddress, multisig.address, ownerA, ownerA.publicKey);
+      fail("Should have failed to delete transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: TransactionAlreadySigned. Error Number: 6004. Error Message: Cannot delete a transaction that has been signed by an owner."));
+    }
+  }).timeout(20000);
+
   it("should not let a non-owner cancel transaction", async () => {
     const multisig = await dsl.createMultisig(2, 3);
     const [ownerA, _ownerB, _ownerC] = multisig.owners;
@@ -183,4 +404,88 @@ describe("Test transaction cancellation", async () => {
 
     await dsl.assertBalance(recipient.publicKey, 1_000_000);
   }).timeout(20000);
+
+  it("should close many stale transactions at once", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+    const refundee = Keypair.generate().publicKey;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: ownerC.publicKey,
+    });
+    const staleTransactions: Array<PublicKey> = [];
+    for (let i = 0; i < 3; i++) {
+      staleTransactions.push(await dsl.proposeTransaction(ownerC, [transactionInstruction], multisig.address));
+    }
+    const rent = (await provider.connection.getBalance(staleTransactions[0])) * staleTransactions.length;
+
+    let setOwnersInstruction = await program.methods
+      .setOwners([ownerA.publicKey, ownerB.publicKey])
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setOwnersInstruction]);
+
+    let bulkCancelInstruction = await program.methods
+      .bulkCancel()
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+        refundee: refundee,
+      })
+      .remainingAccounts(staleTransactions.map(pubkey => ({pubkey, isWritable: true, isSigner: false})))
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [bulkCancelInstruction]);
+
+    for (const transactionAddress of staleTransactions) {
+      assert.strictEqual(await provider.connection.getAccountInfo(transactionAddress), null);
+    }
+    await dsl.assertBalance(refundee, rent);
+  }).timeout(20000);
+
+  it("should record bulk cancelled transactions in the stats and audit log", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [_ownerA, _ownerB, ownerC] = multisig.owners;
+    const refundee = Keypair.generate().publicKey;
+    const stats = await dsl.createStats(multisig.address);
+    const auditLog = await dsl.createAuditLog(multisig.address);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: ownerC.publicKey,
+    });
+    const staleTransactions: Array<PublicKey> = [];
+    for (let i = 0; i < 2; i++) {
+      staleTransactions.push(await dsl.proposeTransaction(ownerC, [transactionInstruction], multisig.address));
+    }
+
+    let bulkCancelInstruction = await program.methods
+      .bulkCancel()
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+        refundee: refundee,
+        auditLog: auditLog,
+        stats: stats,
+      })
+      .remainingAccounts(staleTransactions.map(pubkey => ({pubkey, isWritable: true, isSigner: false})))
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [bulkCancelInstruction]);
+
+    let statsAccount = await program.account.stats.fetch(stats);
+    assert.strictEqual(statsAccount.transactionsCancelled.toNumber(), 2);
+    assert.strictEqual(statsAccount.transactionsExecuted.toNumber(), 1);
+    let auditLogAccount = await program.account.auditLog.fetch(auditLog);
+    assert.deepStrictEqual(
+      auditLogAccount.entries
+        .filter(entry => Object.keys(entry.action)[0] == "cancel")
+        .map(entry => [entry.actor.toBase58(), entry.transaction.toBase58()]),
+      staleTransactions.map(transactionAddress => [multisig.signer.toBase58(), transactionAddress.toBase58()])
+    );
+  }).timeout(20000);
 });
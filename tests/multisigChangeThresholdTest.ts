@@ -1,8 +1,8 @@
 import assert = require("assert");
 import {setUpValidator} from "./utils/before";
 import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
-import {PublicKey,} from "@solana/web3.js";
-import {MultisigDsl} from "./utils/multisigDsl";
+import {PublicKey, SystemProgram, TransactionInstruction} from "@solana/web3.js";
+import {MultisigAccount, MultisigDsl} from "./utils/multisigDsl";
 import {describe} from "mocha";
 import {fail} from "node:assert";
 
@@ -92,6 +92,64 @@ describe("Test changing multisig threshold", async () => {
     assert.ok(threshold.eq(actualMultisig.threshold), "Should have updated threshold");
   }).timeout(20000);
 
+  it("should require the admin threshold to change the threshold", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+
+    let setAdminThreshold = await program.methods
+      .setAdminThreshold(new BN(3))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setAdminThreshold]);
+
+    let changeThresholdTo1 = await program.methods
+      .changeThreshold(new BN(1))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [changeThresholdTo1], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.executeTransaction(transactionAddress, changeThresholdTo1, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NotEnoughSigners. Error Number: 6003. Error Message: Not enough owners signed this transaction"));
+    }
+
+    await dsl.approveTransaction(ownerC, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, changeThresholdTo1, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.strictEqual(actualMultisig.threshold.toNumber(), 1);
+    assert.strictEqual(actualMultisig.adminThreshold.toNumber(), 3);
+  }).timeout(30000);
+
+  it("should not allow an admin threshold lower than the threshold", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+
+    let setAdminThreshold = await program.methods
+      .setAdminThreshold(new BN(1))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    try {
+      await dsl.executeWithQuorum(multisig, [setAdminThreshold]);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidAdminThreshold. Error Number: 6059. Error Message: The admin threshold must be at least the threshold."));
+    }
+  }).timeout(20000);
+
   it("should update threshold for new transactions once executed", async () => {
     const multisig = await dsl.createMultisig(2, 3);
     const [ownerA, ownerB, _ownerC] = multisig.owners;
@@ -355,4 +413,225 @@ describe("Test changing multisig threshold", async () => {
       "Should not have incremented owner set seq number"
     );
   });
+
+  it("should raise the threshold straight away but lower it only after the weakening delay", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+
+    async function changeThresholdInstruction(threshold: number) {
+      return await program.methods
+        .changeThreshold(new BN(threshold))
+        .accounts({
+          multisig: multisig.address,
+          multisigSigner: multisig.signer,
+        })
+        .instruction();
+    }
+
+    let setWeakeningDelayInstruction = await program.methods
+      .setWeakeningDelay(new BN(5))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setWeakeningDelayInstruction, await changeThresholdInstruction(3)]);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.ok(new BN(3).eq(actualMultisig.threshold), "Should have raised threshold straight away");
+
+    await dsl.executeWithQuorum({...multisig, threshold: new BN(3)}, [await changeThresholdInstruction(1)]);
+
+    actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.ok(new BN(3).eq(actualMultisig.threshold), "Should not have lowered threshold yet");
+    assert.ok(new BN(1).eq(actualMultisig.pendingThreshold));
+
+    const finalizeThresholdChange = () => program.methods
+      .finalizeThresholdChange()
+      .accounts({
+        multisig: multisig.address,
+        owner: ownerA.publicKey,
+      })
+      .signers([ownerA])
+      .rpc();
+    try {
+      await finalizeThresholdChange();
+      fail("Should have failed to finalize threshold change");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: WeakeningDelayNotElapsed. Error Number: 6044. Error Message: The weakening delay has not elapsed."));
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 8000));
+    await finalizeThresholdChange();
+
+    actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.ok(new BN(1).eq(actualMultisig.threshold), "Should have lowered threshold");
+    assert.strictEqual(actualMultisig.pendingThreshold, null);
+  }).timeout(40000);
+
+  it("should not lower the threshold straight away by shortening the weakening delay in the same transaction", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+
+    async function setWeakeningDelayInstruction(weakeningDelay: number) {
+      return await program.methods
+        .setWeakeningDelay(new BN(weakeningDelay))
+        .accounts({
+          multisig: multisig.address,
+          multisigSigner: multisig.signer,
+        })
+        .instruction();
+    }
+    let changeThresholdInstruction = await program.methods
+      .changeThreshold(new BN(1))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+
+    await dsl.executeWithQuorum(multisig, [await setWeakeningDelayInstruction(60)]);
+    await dsl.executeWithQuorum(multisig, [await setWeakeningDelayInstruction(0), changeThresholdInstruction]);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.ok(new BN(2).eq(actualMultisig.threshold), "Should not have lowered threshold yet");
+    assert.ok(new BN(1).eq(actualMultisig.pendingThreshold));
+    assert.strictEqual(actualMultisig.weakeningDelay.toNumber(), 60, "Should not have shortened weakening delay yet");
+    assert.strictEqual(actualMultisig.pendingWeakeningDelay.toNumber(), 0);
+
+    try {
+      await program.methods
+        .finalizeWeakeningDelayChange()
+        .accounts({
+          multisig: multisig.address,
+          owner: ownerA.publicKey,
+        })
+        .signers([ownerA])
+        .rpc();
+      fail("Should have failed to finalize weakening delay change");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: WeakeningDelayNotElapsed. Error Number: 6044. Error Message: The weakening delay has not elapsed."));
+    }
+  }).timeout(30000);
+
+  async function assertRequiresAdminThreshold(instruction: (multisig: MultisigAccount) => Promise<TransactionInstruction>) {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+
+    let setAdminThreshold = await program.methods
+      .setAdminThreshold(new BN(3))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setAdminThreshold]);
+
+    let adminInstruction = await instruction(multisig);
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [adminInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.executeTransaction(transactionAddress, adminInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NotEnoughSigners. Error Number: 6003. Error Message: Not enough owners signed this transaction"));
+    }
+
+    await dsl.approveTransaction(ownerC, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, adminInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+  }
+
+  it("should require the admin threshold to set stake weighting", async () => {
+    await assertRequiresAdminThreshold((multisig) => program.methods
+      .setStakeWeighting([], new BN(0), new BN(0))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction());
+  }).timeout(30000);
+
+  it("should require the admin threshold to set an owner's expiry", async () => {
+    await assertRequiresAdminThreshold((multisig) => program.methods
+      .setOwnerExpiry(multisig.owners[2].publicKey, new BN(Math.floor(Date.now() / 1000) + 600))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction());
+  }).timeout(30000);
+
+  it("should require the admin threshold to count the threshold against active owners", async () => {
+    await assertRequiresAdminThreshold((multisig) => program.methods
+      .setThresholdAgainstActive(true)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction());
+  }).timeout(30000);
+
+  it("should require the admin threshold to set the weakening delay", async () => {
+    await assertRequiresAdminThreshold((multisig) => program.methods
+      .setWeakeningDelay(new BN(5))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction());
+  }).timeout(30000);
+
+  it("should require the admin threshold to set the read threshold", async () => {
+    await assertRequiresAdminThreshold((multisig) => program.methods
+      .setReadThreshold(new BN(1))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction());
+  }).timeout(30000);
+
+  it("should require the admin threshold to set the cancel threshold", async () => {
+    await assertRequiresAdminThreshold((multisig) => program.methods
+      .setCancelThreshold(new BN(1))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction());
+  }).timeout(30000);
+
+  it("should require the admin threshold to register a break-glass template", async () => {
+    await assertRequiresAdminThreshold((multisig) => {
+      let evacuate = SystemProgram.transfer({
+        fromPubkey: multisig.signer,
+        lamports: new BN(1_000_000),
+        toPubkey: provider.publicKey,
+      });
+      return program.methods
+        .registerBreakGlass([{programId: evacuate.programId, accounts: evacuate.keys, data: evacuate.data}], multisig.owners[2].publicKey, new BN(5))
+        .accounts({
+          multisig: multisig.address,
+          multisigSigner: multisig.signer,
+          breakGlass: dsl.breakGlassAddress(multisig.address),
+          payer: provider.publicKey,
+        })
+        .instruction();
+    });
+  }).timeout(30000);
+
+  it("should require the admin threshold to close the multisig", async () => {
+    await assertRequiresAdminThreshold((multisig) => program.methods
+      .closeMultisig()
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+        refundee: provider.publicKey,
+      })
+      .instruction());
+  }).timeout(30000);
 });
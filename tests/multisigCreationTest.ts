@@ -1,7 +1,7 @@
 import assert from "assert";
 import {setUpValidator} from "./utils/before";
-import {AnchorProvider, Program} from "@coral-xyz/anchor";
-import {Keypair} from "@solana/web3.js";
+import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
+import {Keypair, PublicKey, SystemProgram, Transaction} from "@solana/web3.js";
 import {MultisigDsl} from "./utils/multisigDsl";
 import {describe} from "mocha";
 import {fail} from "node:assert";
@@ -25,6 +25,7 @@ describe("Test creation of multisig account", async () => {
     assert.ok(multisig.threshold.eq(actualMultisig.threshold));
     assert.deepStrictEqual(actualMultisig.owners, multisig.owners.map(owner => owner.publicKey));
     assert.strictEqual(actualMultisig.ownerSetSeqno, 0);
+    assert.deepStrictEqual(actualMultisig.ownersHash, dsl.ownersHash(multisig.owners.map(owner => owner.publicKey)));
   });
 
   it("should create multiple multisig accounts", async () => {
@@ -46,6 +47,85 @@ describe("Test creation of multisig account", async () => {
     assert.strictEqual(actualMultisig2.ownerSetSeqno, 0);
   });
 
+  it("should create a multisig at the address derived from a seed", async () => {
+    const owners = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const seed = Keypair.generate().publicKey.toBuffer();
+    const [expectedAddress] = PublicKey.findProgramAddressSync([Buffer.from("multisig"), provider.publicKey.toBuffer(), seed], program.programId);
+
+    const multisig = await dsl.createMultisigWithSeed(2, owners, seed);
+
+    assert.ok(multisig.address.equals(expectedAddress));
+    let actualMultisig = await program.account.multisig.fetch(expectedAddress);
+    assert.strictEqual(actualMultisig.nonce, multisig.nonce);
+    assert.ok(multisig.threshold.eq(actualMultisig.threshold));
+    assert.deepStrictEqual(actualMultisig.owners, owners.map(owner => owner.publicKey));
+  });
+
+  it("should not create a second multisig from the same seed", async () => {
+    const owners = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const seed = Keypair.generate().publicKey.toBuffer();
+    await dsl.createMultisigWithSeed(2, owners, seed);
+
+    try {
+      await dsl.createMultisigWithSeed(2, owners, seed);
+      fail("Multisig should not have been created");
+    } catch (e) {
+      assert.ok(e.logs.some((log: string) => log.includes("already in use")));
+    }
+  });
+
+  it("should create a multisig at a different address for another creator using the same seed", async () => {
+    const owners = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const seed = Keypair.generate().publicKey.toBuffer();
+    const creator = Keypair.generate();
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.publicKey,
+          lamports: new BN(100_000_000),
+          toPubkey: creator.publicKey,
+        })
+      )
+    );
+
+    const multisig = await dsl.createMultisigWithSeed(2, owners, seed);
+    const otherMultisig = await dsl.createMultisigWithSeed(2, owners, seed, creator);
+
+    assert.ok(!multisig.address.equals(otherMultisig.address));
+    let actualMultisig = await program.account.multisig.fetch(otherMultisig.address);
+    assert.deepStrictEqual(actualMultisig.owners, owners.map(owner => owner.publicKey));
+  });
+
+  it("should create a multisig with the config exported from another", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const executor = Keypair.generate().publicKey;
+    await dsl.executeWithQuorum(multisig, [
+      await program.methods
+        .setExecutors([executor])
+        .accounts({multisig: multisig.address, multisigSigner: multisig.signer})
+        .instruction(),
+      await program.methods
+        .setMaxPendingSecs(new BN(3600))
+        .accounts({multisig: multisig.address, multisigSigner: multisig.signer})
+        .instruction(),
+      await program.methods
+        .setRequireMemo(true)
+        .accounts({multisig: multisig.address, multisigSigner: multisig.signer})
+        .instruction(),
+    ]);
+
+    const config = await dsl.exportConfig(multisig.address);
+    const migrated = await dsl.createMultisigFromConfig(config, multisig.owners);
+
+    assert.deepStrictEqual(await dsl.exportConfig(migrated.address), config);
+    let actualMultisig = await program.account.multisig.fetch(migrated.address);
+    assert.deepStrictEqual(actualMultisig.owners, multisig.owners.map(owner => owner.publicKey));
+    assert.deepStrictEqual(actualMultisig.executors, [executor]);
+    assert.strictEqual(actualMultisig.maxPendingSecs.toNumber(), 3600);
+    assert.strictEqual(actualMultisig.requireMemo, true);
+    assert.strictEqual(actualMultisig.ownerSetSeqno, 0);
+  }).timeout(20000);
+
   it("should fail to create if provided threshold is greater than number of owners", async () => {
     try {
       await dsl.createMultisig(4, 3);
@@ -76,6 +156,16 @@ describe("Test creation of multisig account", async () => {
     }
   });
 
+  it("should not create multisig with a weight missing for an owner", async () => {
+    try {
+      await dsl.createMultisigWithOwners(2, [Keypair.generate(), Keypair.generate(), Keypair.generate()], 0, 0, [2, 1]);
+      fail("Multisig should not have been created");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidWeights. Error Number: 6052. Error Message: There must be a positive weight for each owner."));
+    }
+  });
+
   it("should not create multisig with duplicate owners", async () => {
     const [ownerA, ownerB] = Array.from({length: 2}, (_, _n) => Keypair.generate());
     try {
@@ -87,6 +177,48 @@ describe("Test creation of multisig account", async () => {
     }
   });
 
+  it("should not create multisig with the zero pubkey as an owner", async () => {
+    const multisig = Keypair.generate();
+    const [_multisigSigner, nonce] = PublicKey.findProgramAddressSync(
+      [multisig.publicKey.toBuffer()],
+      program.programId
+    );
+    try {
+      await program.methods
+        .createMultisig([Keypair.generate().publicKey, PublicKey.default], new BN(1), nonce, new BN(0), null)
+        .accounts({
+          multisig: multisig.publicKey,
+        })
+        .signers([multisig])
+        .rpc();
+      fail("Multisig should not have been created");
+    } catch (e: any) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidOwnerKey. Error Number: 6030. Error Message: The owner key cannot sign as an owner."));
+    }
+  });
+
+  it("should not create multisig with its own signer as an owner", async () => {
+    const multisig = Keypair.generate();
+    const [multisigSigner, nonce] = PublicKey.findProgramAddressSync(
+      [multisig.publicKey.toBuffer()],
+      program.programId
+    );
+    try {
+      await program.methods
+        .createMultisig([Keypair.generate().publicKey, multisigSigner], new BN(1), nonce, new BN(0), null)
+        .accounts({
+          multisig: multisig.publicKey,
+        })
+        .signers([multisig])
+        .rpc();
+      fail("Multisig should not have been created");
+    } catch (e: any) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidOwnerKey. Error Number: 6030."));
+    }
+  });
+
   it("should not create multisig account with bad nonce", async () => {
     try {
       await dsl.createMultisigWithBadNonce(2, 3);
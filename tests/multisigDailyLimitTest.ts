@@ -0,0 +1,112 @@
+import assert from "assert";
+import {setUpValidator} from "./utils/before";
+import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
+import {Keypair, PublicKey, SystemProgram} from "@solana/web3.js";
+import {MultisigAccount, MultisigDsl} from "./utils/multisigDsl";
+import {describe} from "mocha";
+import {fail} from "node:assert";
+
+describe("Test spending within the daily limit", async () => {
+  let provider: AnchorProvider;
+  let program: Program;
+  let dsl: MultisigDsl;
+
+  before(async () => {
+    let result = await setUpValidator(false);
+    program = result.program;
+    provider = result.provider;
+    dsl = new MultisigDsl(program, provider);
+  });
+
+  async function setDailyLimit(multisig: MultisigAccount, dailyLimit: number) {
+    let setDailyLimitInstruction = await program.methods
+      .setDailyLimit(new BN(dailyLimit))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setDailyLimitInstruction]);
+  }
+
+  function transfer(multisig: MultisigAccount, lamports: number, recipient: PublicKey) {
+    return SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(lamports),
+      toPubkey: recipient,
+    });
+  }
+
+  it("should let a single owner transfer within the daily limit", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    const recipient = Keypair.generate().publicKey;
+    await setDailyLimit(multisig, 300_000);
+
+    for (const lamports of [100_000, 200_000]) {
+      let transactionInstruction = transfer(multisig, lamports, recipient);
+      const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+      await dsl.executeWithinLimit(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+    }
+
+    await dsl.assertBalance(recipient, 300_000);
+    await dsl.assertBalance(multisig.signer, 700_000);
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.strictEqual(actualMultisig.spentToday.toNumber(), 300_000);
+  }).timeout(30000);
+
+  it("should not let a single owner transfer more than is left of the daily limit", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    const recipient = Keypair.generate().publicKey;
+    await setDailyLimit(multisig, 300_000);
+
+    let firstInstruction = transfer(multisig, 200_000, recipient);
+    const firstAddress: PublicKey = await dsl.proposeTransaction(ownerA, [firstInstruction], multisig.address);
+    await dsl.executeWithinLimit(firstAddress, firstInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+
+    let secondInstruction = transfer(multisig, 200_000, recipient);
+    const secondAddress: PublicKey = await dsl.proposeTransaction(ownerA, [secondInstruction], multisig.address);
+    try {
+      await dsl.executeWithinLimit(secondAddress, secondInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: DailyLimitExceeded. Error Number: 6061. Error Message: The transfer exceeds what is left of the daily limit."));
+    }
+    await dsl.assertBalance(recipient, 200_000);
+  }).timeout(30000);
+
+  it("should not execute anything but a single transfer within the daily limit", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    const recipient = Keypair.generate().publicKey;
+    await setDailyLimit(multisig, 300_000);
+
+    const instructions = [transfer(multisig, 100_000, recipient), transfer(multisig, 100_000, recipient)];
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, instructions, multisig.address);
+    try {
+      await dsl.executeWithinLimit(transactionAddress, instructions[0], multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NotASingleTransfer. Error Number: 6060. Error Message: Only a single SOL transfer from the multisig signer may be executed within the daily limit."));
+    }
+  }).timeout(30000);
+
+  it("should start the spend of a new day from zero", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    await setDailyLimit(multisig, 300_000);
+
+    // The multisig has never spent within its limit, so its last reset was on a previous day.
+    let transactionInstruction = transfer(multisig, 300_000, Keypair.generate().publicKey);
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.executeWithinLimit(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.strictEqual(actualMultisig.spentToday.toNumber(), 300_000);
+    const now = Math.floor(Date.now() / 1000);
+    assert.ok(Math.abs(actualMultisig.lastReset.toNumber() - now) < 60, "Should have reset the spend on the first transfer of the day");
+  }).timeout(30000);
+});
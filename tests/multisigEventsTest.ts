@@ -0,0 +1,173 @@
+import assert from "assert";
+import {setUpValidator} from "./utils/before";
+import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
+import {Keypair, PublicKey, SystemProgram} from "@solana/web3.js";
+import {MultisigDsl} from "./utils/multisigDsl";
+import {describe} from "mocha";
+
+describe("Test transaction lifecycle events", async () => {
+  let provider: AnchorProvider;
+  let program: Program;
+  let dsl: MultisigDsl;
+
+  before(async () => {
+    let result = await setUpValidator(false);
+    program = result.program;
+    provider = result.provider;
+    dsl = new MultisigDsl(program, provider);
+  });
+
+  // Runs the action and returns the events of the given name it emitted, waiting a while for them to arrive.
+  async function eventsOf<T>(eventName: string, action: () => Promise<T>): Promise<[T, Array<any>]> {
+    const events = [];
+    const listener = program.addEventListener(eventName, (event) => events.push(event));
+    try {
+      const result = await action();
+      for (let attempts = 0; events.length === 0 && attempts < 20; attempts++) {
+        await new Promise((resolve) => setTimeout(resolve, 250));
+      }
+      return [result, events];
+    } finally {
+      await program.removeEventListener(listener);
+    }
+  }
+
+  function assertEvent(event: any, multisig: PublicKey, transaction: PublicKey, actorField: string, actor: PublicKey) {
+    assert.ok(event.multisig.equals(multisig));
+    assert.ok(event.transaction.equals(transaction));
+    assert.ok(event[actorField].equals(actor));
+    assert.strictEqual(event.ownerSetSeqno, 0);
+  }
+
+  it("should emit events as a transaction is created, approved and executed", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const [transactionAddress, created] = await eventsOf("TransactionCreated",
+      () => dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address));
+    assert.strictEqual(created.length, 1);
+    assertEvent(created[0], multisig.address, transactionAddress, "proposer", ownerA.publicKey);
+    assert.strictEqual(created[0].seq.toNumber(), 0);
+
+    const [, approved] = await eventsOf("TransactionApproved",
+      () => dsl.approveTransaction(ownerB, multisig.address, transactionAddress));
+    assert.strictEqual(approved.length, 1);
+    assertEvent(approved[0], multisig.address, transactionAddress, "owner", ownerB.publicKey);
+
+    const [, executed] = await eventsOf("TransactionExecuted",
+      () => dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey));
+    assert.strictEqual(executed.length, 1);
+    assertEvent(executed[0], multisig.address, transactionAddress, "executor", ownerB.publicKey);
+    assert.strictEqual(executed[0].instructionCount, 1);
+  }).timeout(30000);
+
+  it("should emit a single receipt once every instruction of a transaction has executed", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    const instructions = [0, 1, 2].map(() => SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    }));
+    const transactionAddress = await dsl.proposeTransaction(ownerA, instructions, multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    const [, executed] = await eventsOf("TransactionExecuted",
+      () => dsl.executeTransactionWithMultipleInstructions(transactionAddress, instructions, multisig.signer, multisig.address, ownerB, ownerA.publicKey));
+    assert.strictEqual(executed.length, 1);
+    assertEvent(executed[0], multisig.address, transactionAddress, "executor", ownerB.publicKey);
+    assert.strictEqual(executed[0].instructionCount, 3);
+    assert.ok(executed[0].executedAt.toNumber() > 0);
+  }).timeout(30000);
+
+  it("should emit an event when an approval is revoked", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    const [, revoked] = await eventsOf("TransactionRevoked",
+      () => dsl.revokeApproval(ownerB, multisig.address, transactionAddress));
+    assert.strictEqual(revoked.length, 1);
+    assertEvent(revoked[0], multisig.address, transactionAddress, "owner", ownerB.publicKey);
+  }).timeout(30000);
+
+  it("should emit an event when a transaction is cancelled", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    const [, cancelled] = await eventsOf("TransactionCancelled",
+      () => dsl.cancelTransaction(transactionAddress, multisig.address, ownerB, ownerA.publicKey));
+    assert.strictEqual(cancelled.length, 1);
+    assertEvent(cancelled[0], multisig.address, transactionAddress, "executor", ownerB.publicKey);
+  }).timeout(30000);
+
+  it("should emit the previous and new owners when the owners change", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+    const ownerD = Keypair.generate();
+
+    let setOwnersInstruction = await program.methods
+      .setOwners([ownerA.publicKey, ownerB.publicKey, ownerD.publicKey])
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+
+    const [, changed] = await eventsOf("OwnersChanged",
+      () => dsl.executeWithQuorum(multisig, [setOwnersInstruction]));
+    assert.strictEqual(changed.length, 1);
+    assert.ok(changed[0].multisig.equals(multisig.address));
+    assert.deepStrictEqual(changed[0].previousOwners, [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey]);
+    assert.deepStrictEqual(changed[0].owners, [ownerA.publicKey, ownerB.publicKey, ownerD.publicKey]);
+    assert.strictEqual(changed[0].threshold.toNumber(), 2);
+    assert.strictEqual(changed[0].ownerSetSeqno, 1);
+  }).timeout(30000);
+
+  it("should record the time and slot a transaction executed at", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.markRetain(ownerA, transactionAddress);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    const slotBefore = await provider.connection.getSlot("confirmed");
+    const [, executed] = await eventsOf("TransactionExecuted",
+      () => dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey));
+    const slotAfter = await provider.connection.getSlot("confirmed");
+
+    assert.strictEqual(executed.length, 1);
+    const executedSlot = executed[0].executedSlot.toNumber();
+    assert.ok(slotBefore <= executedSlot && executedSlot <= slotAfter, "Should have executed at a slot of the validator");
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.strictEqual(transactionAccount.executedSlot.toNumber(), executedSlot);
+    assert.ok(transactionAccount.executedAt.eq(executed[0].executedAt));
+  }).timeout(30000);
+});
@@ -5,6 +5,7 @@ import {Keypair, PublicKey, SystemProgram, Transaction} from "@solana/web3.js";
 import {
   createInitializeAccountInstruction,
   createTransferCheckedInstruction,
+  createTransferInstruction,
   mintToChecked,
   TOKEN_PROGRAM_ID,
 } from "@solana/spl-token";
@@ -79,6 +80,44 @@ describe("Test transaction execution", async () => {
     await dsl.assertAtaBalance(destinationAta.address, 15);
   }).timeout(20000);
 
+  it("should propose and execute a token transfer built by the multisig", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let mint = await dsl.createTokenMint(3);
+    let multisigOwnedAta = await dsl.createAta(mint, multisig.signer, 20);
+    let destinationAta = await dsl.createAta(mint, Keypair.generate().publicKey);
+
+    const transactionAddress: PublicKey = await dsl.proposeTokenTransfer(ownerA, multisig, multisigOwnedAta.address, destinationAta.address, 15);
+    let tokenTransferInstruction = createTransferInstruction(multisigOwnedAta.address, destinationAta.address, multisig.signer, 15);
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.ok(transactionAccount.instructions[0].programId.equals(TOKEN_PROGRAM_ID));
+    assert.deepStrictEqual(Buffer.from(transactionAccount.instructions[0].data), tokenTransferInstruction.data);
+
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, tokenTransferInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+
+    await dsl.assertAtaBalance(multisigOwnedAta.address, 5);
+    await dsl.assertAtaBalance(destinationAta.address, 15);
+  }).timeout(20000);
+
+  it("should not propose a token transfer out of a token account the multisig doesn't hold", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+
+    let mint = await dsl.createTokenMint(3);
+    let otherAta = await dsl.createAta(mint, Keypair.generate().publicKey, 20);
+    let destinationAta = await dsl.createAta(mint, Keypair.generate().publicKey);
+
+    try {
+      await dsl.proposeTokenTransfer(ownerA, multisig, otherAta.address, destinationAta.address, 15);
+      fail("Should have failed to propose transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidTokenSource. Error Number: 6068. Error Message: The source must be a token account of the given token program held by the multisig signer."));
+    }
+  }).timeout(20000);
+
   it("should let proposer execute a SPL token transaction if multisig approval threshold reached using an auxilliary token account", async () => {
       const multisig: MultisigAccount = await dsl.createMultisig(2, 3);
       const [ownerA, ownerB, _ownerC] = multisig.owners;
@@ -222,6 +261,95 @@ describe("Test transaction execution", async () => {
   }).timeout(20000);
 
 
+  it("should apply instructions in the order they were proposed", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 10_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const recipient = Keypair.generate().publicKey;
+    const vault = await PublicKey.createWithSeed(multisig.signer, "vault", SystemProgram.programId);
+
+    let createVaultInstruction = SystemProgram.createAccountWithSeed({ // creates and funds the vault
+      fromPubkey: multisig.signer,
+      newAccountPubkey: vault,
+      basePubkey: multisig.signer,
+      seed: "vault",
+      lamports: 5_000_000,
+      space: 0,
+      programId: SystemProgram.programId,
+    });
+    let drainVaultInstruction = SystemProgram.transfer({ // only succeeds once the vault has been funded
+      fromPubkey: vault,
+      basePubkey: multisig.signer,
+      toPubkey: recipient,
+      lamports: 5_000_000,
+      seed: "vault",
+      programId: SystemProgram.programId,
+    });
+
+    const outOfOrderAddress: PublicKey = await dsl.proposeTransaction(ownerA, [drainVaultInstruction, createVaultInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, outOfOrderAddress);
+    try {
+      await dsl.executeTransactionWithMultipleInstructions(outOfOrderAddress, [drainVaultInstruction, createVaultInstruction], multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.ok(e.message.includes("Error processing Instruction 0"));
+    }
+    await dsl.assertBalance(multisig.signer, 10_000_000);
+    assert.strictEqual(await provider.connection.getAccountInfo(vault), null);
+
+    const inOrderAddress: PublicKey = await dsl.proposeTransaction(ownerA, [createVaultInstruction, drainVaultInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, inOrderAddress);
+    await dsl.executeTransactionWithMultipleInstructions(inOrderAddress, [createVaultInstruction, drainVaultInstruction], multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+
+    await dsl.assertBalance(multisig.signer, 5_000_000);
+    await dsl.assertBalance(recipient, 5_000_000);
+  }).timeout(20000);
+
+  it("should revert instructions already applied when a later one fails", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 10_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const recipient = Keypair.generate().publicKey;
+    const vault = await PublicKey.createWithSeed(multisig.signer, "vault", SystemProgram.programId);
+
+    let createVaultInstruction = SystemProgram.createAccountWithSeed({ // should work
+      fromPubkey: multisig.signer,
+      newAccountPubkey: vault,
+      basePubkey: multisig.signer,
+      seed: "vault",
+      lamports: 2_000_000,
+      space: 0,
+      programId: SystemProgram.programId,
+    });
+    let transferInstruction = SystemProgram.transfer({ // should work
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: recipient,
+    });
+    let overdrawVaultInstruction = SystemProgram.transfer({ // should fail, not enough funds in the vault
+      fromPubkey: vault,
+      basePubkey: multisig.signer,
+      toPubkey: recipient,
+      lamports: 3_000_000,
+      seed: "vault",
+      programId: SystemProgram.programId,
+    });
+    const instructions = [createVaultInstruction, transferInstruction, overdrawVaultInstruction];
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, instructions, multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    try {
+      await dsl.executeTransactionWithMultipleInstructions(transactionAddress, instructions, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.ok(e.logs.some((log: string) => log.includes("insufficient lamports")));
+    }
+
+    await dsl.assertBalance(multisig.signer, 10_000_000);
+    await dsl.assertBalance(recipient, 0);
+    assert.strictEqual(await provider.connection.getAccountInfo(vault), null);
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.strictEqual(transactionAccount.didExecute, false);
+  }).timeout(20000);
+
   it("should let owner who has approved execute transaction if multisig approval threshold reached", async () => {
     const multisig = await dsl.createMultisig(2, 3, 1_000_000);
     const [ownerA, ownerB, _ownerC] = multisig.owners;
@@ -281,7 +409,7 @@ describe("Test transaction execution", async () => {
 
     await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
 
-    await dsl.assertBalance(ownerA.publicKey, 2_108_880);  // this is the rent exemption amount
+    await dsl.assertBalance(ownerA.publicKey, 3_187_680);  // this is the rent exemption amount
 
     let transactionActInfo = await provider.connection.getAccountInfo(transactionAddress, "confirmed");
     assert.strictEqual(transactionActInfo, null);
@@ -306,7 +434,7 @@ describe("Test transaction execution", async () => {
 
     await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, otherAccount.publicKey);
 
-    await dsl.assertBalance(otherAccount.publicKey, 2_108_880);  // this is the rent exemption amount
+    await dsl.assertBalance(otherAccount.publicKey, 3_187_680);  // this is the rent exemption amount
   }).timeout(20000);
 
   it("should not clear up transaction account if execute fails", async () => {
@@ -364,6 +492,130 @@ describe("Test transaction execution", async () => {
   }).timeout(20000);
 
 
+  it("should not let an instruction re-enter execution of the same transaction", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const transactionKeypair = Keypair.generate();
+
+    let reentrantInstruction = await program.methods
+      .executeTransaction()
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+        transaction: transactionKeypair.publicKey,
+        executor: ownerB.publicKey,
+        refundee: ownerA.publicKey
+      })
+      .instruction();
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [reentrantInstruction], multisig.address, transactionKeypair);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.executeTransaction(transactionAddress, reentrantInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: AlreadyExecuted. Error Number: 6007. Error Message: The given transaction has already been executed"));
+    }
+
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.ok(!transactionAccount.didExecute, "Transaction should not have been marked executed");
+  }).timeout(20000);
+
+  it("should not execute with a multisig signer derived from a non-canonical nonce", async () => {
+    const multisig = await dsl.createMultisigWithNonCanonicalNonce(2, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.publicKey,
+          lamports: new BN(1_000_000),
+          toPubkey: multisig.signer,
+        })
+      )
+    );
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidSignerBump. Error Number: 6041. Error Message: The multisig nonce is not the canonical bump of its signer."));
+    }
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+  }).timeout(20000);
+
+  it("should recover a multisig created with a non-canonical nonce by resetting it", async () => {
+    const multisig = await dsl.createMultisigWithNonCanonicalNonce(2, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.publicKey,
+          lamports: new BN(1_000_000),
+          toPubkey: multisig.signer,
+        })
+      )
+    );
+    const [canonicalSigner, canonicalNonce] = PublicKey.findProgramAddressSync([multisig.address.toBuffer()], program.programId);
+
+    // Sweep what the old signer holds over to the canonical one before it can no longer sign.
+    let sweepInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: canonicalSigner,
+    });
+    let resetNonceInstruction = await program.methods
+      .resetNonce(canonicalNonce)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    const instructions = [sweepInstruction, resetNonceInstruction];
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, instructions, multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await dsl.executeTransactionWithMultipleInstructions(transactionAddress, instructions, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.strictEqual(actualMultisig.nonce, canonicalNonce);
+    await dsl.assertBalance(canonicalSigner, 1_000_000);
+  }).timeout(20000);
+
+  it("should not reset the nonce to anything but the canonical bump", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let resetNonceInstruction = await program.methods
+      .resetNonce(multisig.nonce - 1)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [resetNonceInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    try {
+      await dsl.executeTransaction(transactionAddress, resetNonceInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidNonce. Error Number: 6069. Error Message: The nonce is not the canonical bump of the multisig signer."));
+    }
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.strictEqual(actualMultisig.nonce, multisig.nonce);
+  }).timeout(20000);
+
   it("should not let a non-owner execute transaction", async () => {
     const multisig = await dsl.createMultisig(2, 3, 1_000_000);
     const [ownerA, ownerB, _ownerC] = multisig.owners;
@@ -390,4 +642,597 @@ describe("Test transaction execution", async () => {
 
     await dsl.assertBalance(multisig.signer, 1_000_000);
   }).timeout(20000);
+
+  it("should only let the designated executor execute a transaction", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+    const outsider = Keypair.generate();
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address, undefined, {designatedExecutor: ownerC.publicKey});
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    assert.strictEqual(await dsl.canExecute(multisig.address, transactionAddress, ownerA.publicKey), false);
+    assert.strictEqual(await dsl.canExecute(multisig.address, transactionAddress, ownerB.publicKey), false);
+    assert.strictEqual(await dsl.canExecute(multisig.address, transactionAddress, ownerC.publicKey), true);
+    assert.strictEqual(await dsl.canExecute(multisig.address, transactionAddress, outsider.publicKey), false);
+
+    try {
+      await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidExecutor. Error Number: 6010."));
+    }
+
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerC, ownerA.publicKey);
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(20000);
+
+  it("should let a non-owner executor execute but not approve a transaction", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const keeper = Keypair.generate();
+
+    let setExecutorsInstruction = await program.methods
+      .setExecutors([keeper.publicKey])
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setExecutorsInstruction]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    try {
+      await dsl.approveTransaction(keeper, multisig.address, transactionAddress);
+      fail("Should have failed to approve transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidOwner. Error Number: 6000. Error Message: The given owner is not part of this multisig"));
+    }
+
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, keeper, ownerA.publicKey);
+
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(20000);
+
+  it("should pay the keeper reward to a non-owner keeper under public execution", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const keeper = Keypair.generate();
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address, undefined, {keeperReward: new BN(1_000_000)});
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, keeper, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message, new RegExp(".*Error Code: InvalidExecutor.*"));
+    }
+
+    let setPublicExecutionInstruction = await program.methods
+      .setPublicExecution(true)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setPublicExecutionInstruction]);
+
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, keeper, ownerA.publicKey);
+
+    await dsl.assertBalance(keeper.publicKey, 1_000_000);
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(20000);
+
+  it("should not let the proposer execute a transaction under strict separation of duties", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let setStrictSeparationInstruction = await program.methods
+      .setStrictSeparation(true)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setStrictSeparationInstruction]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: SeparationOfDutiesViolated. Error Number: 6051. Error Message: The executor may not also be the proposer or the only approver."));
+    }
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(20000);
+
+  it("should not leave the multisig signer below rent-exemption when required", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 2_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let setRequireSignerRentExemptionInstruction = await program.methods
+      .setRequireSignerRentExemption(true)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setRequireSignerRentExemptionInstruction]);
+
+    let drainInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_500_000),
+      toPubkey: provider.publicKey,
+    });
+    const drainAddress: PublicKey = await dsl.proposeTransaction(ownerA, [drainInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, drainAddress);
+
+    try {
+      await dsl.executeTransaction(drainAddress, drainInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: WouldBreakRentExemption. Error Number: 6028. Error Message: The transaction would leave its signer below rent-exemption."));
+    }
+    await dsl.assertBalance(multisig.signer, 2_000_000);
+
+    let transferInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: provider.publicKey,
+    });
+    const transferAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transferInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transferAddress);
+    await dsl.executeTransaction(transferAddress, transferInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+  }).timeout(20000);
+
+  it("should keep a transaction marked for retention once executed", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    try {
+      await dsl.markRetain(ownerB, transactionAddress);
+      fail("Should have failed to mark transaction for retention");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NotProposer. Error Number: 6031. Error Message: Only the proposer of the transaction may do this."));
+    }
+
+    await dsl.markRetain(ownerA, transactionAddress);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+
+    await dsl.assertBalance(multisig.signer, 900_000);
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.strictEqual(transactionAccount.retain, true);
+    assert.strictEqual(transactionAccount.didExecute, true);
+  }).timeout(20000);
+
+  it("should execute an auto-executing transaction with the approval which reaches threshold", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.markAutoExecute(ownerA, transactionAddress);
+
+    await dsl.approveAndExecuteTransaction(ownerB, multisig.address, multisig.signer, transactionAddress, [transactionInstruction], ownerA.publicKey);
+
+    await dsl.assertBalance(multisig.signer, 900_000);
+    assert.strictEqual(await provider.connection.getAccountInfo(transactionAddress, "confirmed"), null);
+  }).timeout(20000);
+
+  it("should only approve an auto-executing transaction reaching threshold without the accounts to execute it", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.markAutoExecute(ownerA, transactionAddress);
+
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.signers, [true, true, false]);
+    assert.strictEqual(transactionAccount.didExecute, false);
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(20000);
+
+  it("should only approve an auto-executing transaction reaching threshold which can't execute yet", async () => {
+    const [ownerA, ownerB, ownerC] = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const multisig = await dsl.createMultisigWithOwners(2, [ownerA, ownerB, ownerC], 1_000_000, 60);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.markAutoExecute(ownerA, transactionAddress);
+
+    await dsl.approveAndExecuteTransaction(ownerB, multisig.address, multisig.signer, transactionAddress, [transactionInstruction], ownerA.publicKey);
+
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.signers, [true, true, false]);
+    assert.strictEqual(transactionAccount.didExecute, false);
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+  }).timeout(20000);
+
+  it("should approve and execute a transaction in one call by the owner reaching threshold", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    await dsl.approveAndExecute(ownerB, multisig.address, multisig.signer, transactionAddress, [transactionInstruction], ownerA.publicKey);
+
+    await dsl.assertBalance(multisig.signer, 900_000);
+    assert.strictEqual(await provider.connection.getAccountInfo(transactionAddress, "confirmed"), null);
+  }).timeout(20000);
+
+  it("should only approve a transaction in a call to approve and execute which doesn't reach threshold", async () => {
+    const multisig = await dsl.createMultisig(3, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    await dsl.approveAndExecute(ownerB, multisig.address, multisig.signer, transactionAddress, [transactionInstruction], ownerA.publicKey);
+
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.signers, [true, true, false]);
+    assert.strictEqual(transactionAccount.didExecute, false);
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+  }).timeout(20000);
+
+  it("should execute a transaction one instruction at a time", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const recipients = [Keypair.generate().publicKey, Keypair.generate().publicKey, Keypair.generate().publicKey];
+
+    const instructions = recipients.map(recipient => SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: recipient,
+    }));
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, instructions, multisig.address, undefined, {allowOutOfOrder: true});
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    await dsl.executeTransactionByIndex(transactionAddress, 1, instructions[1], multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.executedMask, [false, true, false]);
+    assert.strictEqual(transactionAccount.didExecute, false);
+    await dsl.assertBalance(recipients[1], 100_000);
+
+    await dsl.executeTransactionByIndex(transactionAddress, 0, instructions[0], multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+    transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.executedMask, [true, true, false]);
+
+    await dsl.executeTransactionByIndex(transactionAddress, 2, instructions[2], multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+    for (const recipient of recipients) {
+      await dsl.assertBalance(recipient, 100_000);
+    }
+    await dsl.assertBalance(multisig.signer, 700_000);
+    assert.strictEqual(await provider.connection.getAccountInfo(transactionAddress, "confirmed"), null);
+  }).timeout(30000);
+
+  it("should not execute an instruction before those preceding it unless allowed", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    const instructions = [0, 1].map(() => SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    }));
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, instructions, multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.executeTransactionByIndex(transactionAddress, 1, instructions[1], multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InstructionOutOfOrder. Error Number: 6073. Error Message: Instructions before this one have not been executed yet."));
+    }
+
+    await dsl.executeTransactionByIndex(transactionAddress, 0, instructions[0], multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+    await dsl.executeTransactionByIndex(transactionAddress, 1, instructions[1], multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+    await dsl.assertBalance(multisig.signer, 800_000);
+  }).timeout(30000);
+
+  it("should not execute an instruction out of range or already executed", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    const instructions = [0, 1].map(() => SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    }));
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, instructions, multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.executeTransactionByIndex(transactionAddress, 2, instructions[0], multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidInstructionIndex. Error Number: 6054. Error Message: The transaction has no instruction at the given index."));
+    }
+
+    await dsl.executeTransactionByIndex(transactionAddress, 0, instructions[0], multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+    try {
+      await dsl.executeTransactionByIndex(transactionAddress, 0, instructions[0], multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: AlreadyExecuted. Error Number: 6007. Error Message: The given transaction has already been executed"));
+    }
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(30000);
+
+  it("should not execute an instruction of a transaction which has not reached threshold", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    try {
+      await dsl.executeTransactionByIndex(transactionAddress, 0, transactionInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NotEnoughSigners. Error Number: 6003. Error Message: Not enough owners signed this transaction"));
+    }
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+  }).timeout(20000);
+
+  it("should not execute an instruction of another program which writes to the multisig account", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: multisig.address,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: MultisigAccountProtected. Error Number: 6058. Error Message: Only instructions of this program may write to the multisig account."));
+    }
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+  }).timeout(20000);
+
+  it("should not execute a retained transaction a second time", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.markRetain(ownerA, transactionAddress);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+
+    try {
+      await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: AlreadyExecuted. Error Number: 6007. Error Message: The given transaction has already been executed"));
+    }
+
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(20000);
+
+  it("should let a rent sponsor fund accounts created by a transaction", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const rentSponsor = Keypair.generate();
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.publicKey,
+          lamports: new BN(10_000_000),
+          toPubkey: rentSponsor.publicKey,
+        })
+      )
+    );
+
+    const space = 64;
+    const rent = await provider.connection.getMinimumBalanceForRentExemption(space);
+    const vault = await PublicKey.createWithSeed(multisig.signer, "vault", SystemProgram.programId);
+    let createAccountInstruction = SystemProgram.createAccountWithSeed({
+      fromPubkey: rentSponsor.publicKey,
+      newAccountPubkey: vault,
+      basePubkey: multisig.signer,
+      seed: "vault",
+      lamports: rent,
+      space: space,
+      programId: SystemProgram.programId,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [createAccountInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await dsl.executeTransactionWithMultipleInstructions(transactionAddress, [createAccountInstruction], multisig.signer, multisig.address, ownerB, ownerA.publicKey, undefined, [rentSponsor]);
+
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+    await dsl.assertBalance(vault, rent);
+    await dsl.assertBalance(rentSponsor.publicKey, 10_000_000 - rent);
+  }).timeout(20000);
+
+  it("should only execute a transaction once every co-multisig has approved it", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const coMultisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address, undefined, {coMultisigs: [coMultisig.address]});
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: CoApprovalMissing. Error Number: 6045. Error Message: Not every co-multisig has approved this transaction."));
+    }
+
+    let coApproveInstruction = await program.methods
+      .coApprove()
+      .accounts({
+        transaction: transactionAddress,
+        coMultisig: coMultisig.address,
+        coMultisigSigner: coMultisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(coMultisig, [coApproveInstruction]);
+
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.coApprovals, [true]);
+
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(40000);
+
+  it("should report a transaction executable once enough owners have approved it", async () => {
+    const multisig = await dsl.createMultisig(3, 3, 1_000_000);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    assert.strictEqual(await dsl.checkExecutable(multisig.address, transactionAddress), false);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    assert.strictEqual(await dsl.checkExecutable(multisig.address, transactionAddress), false);
+    await dsl.approveTransaction(ownerC, multisig.address, transactionAddress);
+    assert.strictEqual(await dsl.checkExecutable(multisig.address, transactionAddress), true);
+
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(30000);
+
+  it("should name an account referenced by the transaction which wasn't passed", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const recipient = Keypair.generate().publicKey;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: recipient,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await program.methods
+        .executeTransaction()
+        .accounts({
+          multisig: multisig.address,
+          multisigSigner: multisig.signer,
+          transaction: transactionAddress,
+          executor: ownerB.publicKey,
+          refundee: ownerA.publicKey,
+          auditLog: dsl.auditLog(multisig.address),
+          stats: dsl.stats(multisig.address),
+        })
+        .remainingAccounts(dsl.executionAccounts([transactionInstruction], [multisig.signer])
+          .filter(account => !account.pubkey.equals(recipient)))
+        .signers([ownerB])
+        .rpc();
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: MissingRemainingAccount. Error Number: 6067. Error Message: An account referenced by the transaction's instructions was not passed."));
+      assert.ok(e.logs.some((log: string) => log.includes(`Missing remaining account: ${recipient.toBase58()}`)));
+    }
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+  }).timeout(30000);
 });
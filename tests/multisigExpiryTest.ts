@@ -0,0 +1,236 @@
+import assert from "assert";
+import {setUpValidator} from "./utils/before";
+import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
+import {Keypair, PublicKey, SystemProgram} from "@solana/web3.js";
+import {MultisigAccount, MultisigDsl} from "./utils/multisigDsl";
+import {describe} from "mocha";
+import {fail} from "node:assert";
+
+describe("Test transaction expiry", async () => {
+  let provider: AnchorProvider;
+  let program: Program;
+  let dsl: MultisigDsl;
+
+  before(async () => {
+    let result = await setUpValidator(false);
+    program = result.program;
+    provider = result.provider;
+    dsl = new MultisigDsl(program, provider);
+  });
+
+  async function setMaxPendingSecs(multisig: MultisigAccount, maxPendingSecs: number) {
+    let setMaxPendingSecsInstruction = await program.methods
+      .setMaxPendingSecs(new BN(maxPendingSecs))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setMaxPendingSecsInstruction]);
+  }
+
+  async function setDefaultExpirySecs(multisig: MultisigAccount, defaultExpirySecs: number) {
+    let setDefaultExpirySecsInstruction = await program.methods
+      .setDefaultExpirySecs(new BN(defaultExpirySecs))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setDefaultExpirySecsInstruction]);
+  }
+
+  function transfer(multisig: MultisigAccount) {
+    return SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+  }
+
+  it("should not expire transactions by default", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transfer(multisig)], multisig.address);
+
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.ok(new BN("9223372036854775807").eq(transactionAccount.expiresAt));
+  }).timeout(20000);
+
+  it("should default expiry to the maximum pending time", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    await setMaxPendingSecs(multisig, 3600);
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transfer(multisig)], multisig.address);
+
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.ok(transactionAccount.createdAt.addn(3600).eq(transactionAccount.expiresAt));
+  }).timeout(20000);
+
+  it("should report the default expiry as capped by the maximum pending time", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    await setDefaultExpirySecs(multisig, 7200);
+    await setMaxPendingSecs(multisig, 3600);
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transfer(multisig)], multisig.address);
+
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    const effectiveExpiry = await dsl.effectiveExpiry(multisig.address, transactionAddress);
+    assert.ok(transactionAccount.createdAt.addn(3600).eq(effectiveExpiry));
+  }).timeout(20000);
+
+  it("should inherit the configured default expiry when none is given", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    await setMaxPendingSecs(multisig, 3600);
+    await setDefaultExpirySecs(multisig, 600);
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transfer(multisig)], multisig.address);
+    const zeroExpiryAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transfer(multisig)], multisig.address, undefined, {expiresAt: new BN(0)});
+
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.ok(transactionAccount.createdAt.addn(600).eq(transactionAccount.expiresAt));
+    let zeroExpiryAccount = await program.account.transaction.fetch(zeroExpiryAddress);
+    assert.ok(zeroExpiryAccount.createdAt.addn(600).eq(zeroExpiryAccount.expiresAt));
+  }).timeout(20000);
+
+  it("should reject an expiry beyond the maximum pending time", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    await setMaxPendingSecs(multisig, 3600);
+
+    const tooLate = new BN(Math.floor(Date.now() / 1000) + 7200);
+    try {
+      await dsl.proposeTransaction(ownerA, [transfer(multisig)], multisig.address, undefined, {expiresAt: tooLate});
+      fail("Should have failed to propose transaction");
+    } catch (e) {
+      assert.ok(e.message.includes("Transaction expiry is beyond the maximum pending time of the multisig."));
+    }
+  }).timeout(20000);
+
+  it("should accept an expiry within the maximum pending time", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    await setMaxPendingSecs(multisig, 3600);
+
+    const expiresAt = new BN(Math.floor(Date.now() / 1000) + 600);
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transfer(multisig)], multisig.address, undefined, {expiresAt});
+
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.ok(expiresAt.eq(transactionAccount.expiresAt));
+  }).timeout(20000);
+
+  it("should not execute an expired transaction", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const transactionInstruction = transfer(multisig);
+
+    const expiresAt = new BN(Math.floor(Date.now() / 1000) + 2);
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address, undefined, {expiresAt});
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await new Promise((resolve) => setTimeout(resolve, 5000));
+
+    try {
+      await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.ok(e.message.includes("Transaction has expired."));
+    }
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+  }).timeout(20000);
+
+  it("should cancel an expired transaction to reclaim its rent", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const refundee = Keypair.generate().publicKey;
+
+    const expiresAt = new BN(Math.floor(Date.now() / 1000) + 2);
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transfer(multisig)], multisig.address, undefined, {expiresAt});
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+
+    try {
+      await dsl.executeTransaction(transactionAddress, transfer(multisig), multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.ok(e.message.includes("Transaction has expired."));
+    }
+
+    await dsl.cancelTransaction(transactionAddress, multisig.address, ownerB, refundee);
+
+    await dsl.assertBalance(refundee, 3_187_680); // this is the rent exemption amount
+    assert.strictEqual(await provider.connection.getAccountInfo(transactionAddress, "confirmed"), null);
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+  }).timeout(20000);
+
+  it("should report a new transaction as executable now", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transfer(multisig)], multisig.address);
+
+    const timeUntilExecutable = await dsl.timeUntilExecutable(multisig.address, transactionAddress);
+    assert.ok(timeUntilExecutable.lten(0), "Should be executable now");
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+    assert.ok((await dsl.timeUntilExecutable(multisig.address, transactionAddress)).lt(timeUntilExecutable), "Should count down as the clock advances");
+  }).timeout(20000);
+
+  it("should not execute a transaction before the minimum delay set at creation", async () => {
+    const multisig = await dsl.createMultisigWithOwners(2, [Keypair.generate(), Keypair.generate(), Keypair.generate()], 1_000_000, 5);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.strictEqual(actualMultisig.minDelay.toNumber(), 5);
+    assert.strictEqual(actualMultisig.quarantineSecs.toNumber(), 0);
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transfer(multisig)], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    assert.ok((await dsl.timeUntilExecutable(multisig.address, transactionAddress)).gtn(0), "Should be timelocked");
+
+    try {
+      await dsl.executeTransaction(transactionAddress, transfer(multisig), multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: TimelockNotElapsed. Error Number: 6075. Error Message: The minimum delay after creating the transaction has not elapsed."));
+    }
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+
+    await new Promise((resolve) => setTimeout(resolve, 7000));
+    await dsl.executeTransaction(transactionAddress, transfer(multisig), multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(40000);
+
+  it("should not count approvals until the quarantine has elapsed", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    let setQuarantineSecsInstruction = await program.methods
+      .setQuarantineSecs(new BN(5))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setQuarantineSecsInstruction]);
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transfer(multisig)], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    assert.ok((await dsl.timeUntilExecutable(multisig.address, transactionAddress)).gtn(0), "Should be in quarantine");
+
+    try {
+      await dsl.executeTransaction(transactionAddress, transfer(multisig), multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InQuarantine. Error Number: 6049. Error Message: The transaction is still in quarantine."));
+    }
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+
+    await new Promise((resolve) => setTimeout(resolve, 7000));
+    await dsl.executeTransaction(transactionAddress, transfer(multisig), multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(40000);
+});
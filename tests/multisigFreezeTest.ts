@@ -0,0 +1,173 @@
+import assert from "assert";
+import {setUpValidator} from "./utils/before";
+import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
+import {Keypair, PublicKey, SystemProgram} from "@solana/web3.js";
+import {MultisigAccount, MultisigDsl} from "./utils/multisigDsl";
+import {describe} from "mocha";
+import {fail} from "node:assert";
+
+describe("Test freezing a multisig", async () => {
+  let provider: AnchorProvider;
+  let program: Program;
+  let dsl: MultisigDsl;
+
+  before(async () => {
+    let result = await setUpValidator(false);
+    program = result.program;
+    provider = result.provider;
+    dsl = new MultisigDsl(program, provider);
+  });
+
+  function transfer(multisig: MultisigAccount) {
+    return SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+  }
+
+  it("should let a single owner freeze execution", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+
+    let transactionInstruction = transfer(multisig);
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    await dsl.freeze(multisig.address, ownerC);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.strictEqual(actualMultisig.frozen, true);
+    try {
+      await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: MultisigFrozen. Error Number: 6065. Error Message: The multisig is frozen, so may only act on a transaction unfreezing it."));
+    }
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+  }).timeout(30000);
+
+  it("should not let owners propose, approve or revoke approvals of transactions while frozen", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+
+    let transactionInstruction = transfer(multisig);
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.freeze(multisig.address, ownerC);
+
+    try {
+      await dsl.proposeTransaction(ownerA, [transfer(multisig)], multisig.address);
+      fail("Should have failed to propose transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: MultisigFrozen. Error Number: 6065. Error Message: The multisig is frozen, so may only act on a transaction unfreezing it."));
+    }
+    try {
+      await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+      fail("Should have failed to approve transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: MultisigFrozen. Error Number: 6065. Error Message: The multisig is frozen, so may only act on a transaction unfreezing it."));
+    }
+    try {
+      await dsl.approveManyTransactions(ownerB, multisig.address, [transactionAddress]);
+      fail("Should have failed to approve transactions");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: MultisigFrozen. Error Number: 6065. Error Message: The multisig is frozen, so may only act on a transaction unfreezing it."));
+    }
+    try {
+      await dsl.revokeApproval(ownerA, multisig.address, transactionAddress);
+      fail("Should have failed to revoke approval");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: MultisigFrozen. Error Number: 6065. Error Message: The multisig is frozen, so may only act on a transaction unfreezing it."));
+    }
+
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.signers, [true, false, false]);
+  }).timeout(30000);
+
+  it("should not let anyone but an owner freeze execution", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+
+    try {
+      await dsl.freeze(multisig.address, Keypair.generate());
+      fail("Should have failed to freeze multisig");
+    } catch (e) {
+      assert.match(e.message, new RegExp(".*Error Code: InvalidOwner."));
+    }
+  }).timeout(30000);
+
+  it("should not let an owner whose signing rights have expired freeze execution", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [_ownerA, _ownerB, ownerC] = multisig.owners;
+
+    let setOwnerExpiryInstruction = await program.methods
+      .setOwnerExpiry(ownerC.publicKey, new BN(Math.floor(Date.now() / 1000) + 2))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setOwnerExpiryInstruction]);
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+
+    try {
+      await dsl.freeze(multisig.address, ownerC);
+      fail("Should have failed to freeze multisig");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: OwnerExpired. Error Number: 6023. Error Message: The owner's signing rights have expired."));
+    }
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.strictEqual(actualMultisig.frozen, false);
+  }).timeout(30000);
+
+  it("should not let a single owner unfreeze execution", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    await dsl.freeze(multisig.address, ownerA);
+
+    try {
+      await program.methods
+        .unfreeze()
+        .accounts({
+          multisig: multisig.address,
+          multisigSigner: multisig.signer,
+        })
+        .rpc();
+      fail("Should have failed to unfreeze multisig");
+    } catch (e) {
+      assert.match(e.message, new RegExp("Signature verification failed"));
+    }
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.strictEqual(actualMultisig.frozen, true);
+  }).timeout(30000);
+
+  it("should resume execution once unfrozen by a quorum", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+    await dsl.freeze(multisig.address, ownerC);
+
+    let unfreezeInstruction = await program.methods
+      .unfreeze()
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [unfreezeInstruction]);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.strictEqual(actualMultisig.frozen, false);
+    let transactionInstruction = transfer(multisig);
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(30000);
+});
@@ -0,0 +1,110 @@
+import assert from "assert";
+import {setUpValidator} from "./utils/before";
+import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
+import {Keypair, PublicKey, SystemProgram} from "@solana/web3.js";
+import {MultisigAccount, MultisigDsl} from "./utils/multisigDsl";
+import {describe} from "mocha";
+import {fail} from "node:assert";
+
+describe("Test SOL outflow cap", async () => {
+  let provider: AnchorProvider;
+  let program: Program;
+  let dsl: MultisigDsl;
+
+  before(async () => {
+    let result = await setUpValidator(false);
+    program = result.program;
+    provider = result.provider;
+    dsl = new MultisigDsl(program, provider);
+  });
+
+  async function setMaxSolOutflow(multisig: MultisigAccount, maxSolOutflow: number) {
+    let setMaxSolOutflowInstruction = await program.methods
+      .setMaxSolOutflow(new BN(maxSolOutflow))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setMaxSolOutflowInstruction]);
+  }
+
+  it("should not cap outflow by default", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.ok(new BN("18446744073709551615").eq(actualMultisig.maxSolOutflow));
+  });
+
+  it("should reject a transaction transferring more than the cap", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 2_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    await setMaxSolOutflow(multisig, 1_000_000);
+
+    let transfer1 = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(600_000),
+      toPubkey: provider.publicKey,
+    });
+    let transfer2 = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(600_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transfer1, transfer2], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.executeTransactionWithMultipleInstructions(transactionAddress, [transfer1, transfer2], multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: OutflowCapExceeded. Error Number: 6013. Error Message: The transaction transfers more SOL than the multisig allows per transaction."));
+    }
+    await dsl.assertBalance(multisig.signer, 2_000_000);
+  }).timeout(20000);
+
+  it("should cap the outflow of a transaction executed one instruction at a time", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 2_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    await setMaxSolOutflow(multisig, 1_000_000);
+
+    const instructions = [0, 1].map(() => SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(600_000),
+      toPubkey: provider.publicKey,
+    }));
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, instructions, multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.executeTransactionByIndex(transactionAddress, 0, instructions[0], multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: OutflowCapExceeded. Error Number: 6013. Error Message: The transaction transfers more SOL than the multisig allows per transaction."));
+    }
+    await dsl.assertBalance(multisig.signer, 2_000_000);
+  }).timeout(20000);
+
+  it("should allow a transaction transferring up to the cap", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 2_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const recipient = Keypair.generate().publicKey;
+    await setMaxSolOutflow(multisig, 1_000_000);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(1_000_000),
+      toPubkey: recipient,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+    await dsl.assertBalance(recipient, 1_000_000);
+  }).timeout(20000);
+});
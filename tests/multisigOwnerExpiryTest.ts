@@ -0,0 +1,205 @@
+import assert from "assert";
+import {setUpValidator} from "./utils/before";
+import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
+import {Keypair, PublicKey, SystemProgram} from "@solana/web3.js";
+import {MultisigAccount, MultisigDsl} from "./utils/multisigDsl";
+import {describe} from "mocha";
+import {fail} from "node:assert";
+
+describe("Test temporary owners", async () => {
+  let provider: AnchorProvider;
+  let program: Program;
+  let dsl: MultisigDsl;
+
+  before(async () => {
+    let result = await setUpValidator(false);
+    program = result.program;
+    provider = result.provider;
+    dsl = new MultisigDsl(program, provider);
+  });
+
+  async function addTemporaryOwner(multisig: MultisigAccount, temporaryOwner: Keypair, expiresAt: BN) {
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    let setOwnersInstruction = await program.methods
+      .setOwners([ownerA.publicKey, ownerB.publicKey, temporaryOwner.publicKey])
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    let setOwnerExpiryInstruction = await program.methods
+      .setOwnerExpiry(temporaryOwner.publicKey, expiresAt)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setOwnersInstruction, setOwnerExpiryInstruction]);
+  }
+
+  it("should stop counting the approval of a temporary owner once expired", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const contractor = Keypair.generate();
+    const expiresAt = new BN(Math.floor(Date.now() / 1000) + 6);
+    await addTemporaryOwner(multisig, contractor, expiresAt);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.ownerExpiries.map(expiry => expiry?.toString() ?? null), [null, null, expiresAt.toString()]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(contractor, multisig.address, transactionAddress);
+
+    await new Promise((resolve) => setTimeout(resolve, 9000));
+
+    try {
+      await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NotEnoughSigners. Error Number: 6003."));
+    }
+
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(40000);
+
+  it("should not allow an expired owner to approve", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    const contractor = Keypair.generate();
+    await addTemporaryOwner(multisig, contractor, new BN(Math.floor(Date.now() / 1000) + 3));
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    await new Promise((resolve) => setTimeout(resolve, 6000));
+
+    try {
+      await dsl.approveTransaction(contractor, multisig.address, transactionAddress);
+      fail("Should have failed to approve transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: OwnerExpired. Error Number: 6023. Error Message: The owner's signing rights have expired."));
+    }
+  }).timeout(40000);
+
+  it("should measure the threshold against active owners when configured", async () => {
+    const multisig = await dsl.createMultisig(3, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const contractor = Keypair.generate();
+
+    let setThresholdAgainstActiveInstruction = await program.methods
+      .setThresholdAgainstActive(true)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setThresholdAgainstActiveInstruction]);
+    await addTemporaryOwner(multisig, contractor, new BN(Math.floor(Date.now() / 1000) + 3));
+
+    await new Promise((resolve) => setTimeout(resolve, 6000));
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+
+    await dsl.assertBalance(multisig.signer, 900_000);
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.ok(new BN(3).eq(actualMultisig.threshold), "Should not have changed the configured threshold");
+  }).timeout(40000);
+
+  it("should report the effective threshold of a transaction", async () => {
+    const multisig = await dsl.createMultisig(3, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    const contractor = Keypair.generate();
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    assert.ok(new BN(3).eq(await dsl.effectiveThreshold(multisig.address, transactionAddress)));
+
+    let setThresholdAgainstActiveInstruction = await program.methods
+      .setThresholdAgainstActive(true)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setThresholdAgainstActiveInstruction]);
+    await addTemporaryOwner(multisig, contractor, new BN(Math.floor(Date.now() / 1000) + 3));
+
+    const laterTransactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    assert.ok(new BN(3).eq(await dsl.effectiveThreshold(multisig.address, laterTransactionAddress)));
+
+    await new Promise((resolve) => setTimeout(resolve, 6000));
+
+    assert.ok(new BN(2).eq(await dsl.effectiveThreshold(multisig.address, laterTransactionAddress)), "Should not count the expired owner");
+  }).timeout(40000);
+
+  it("should keep the threshold a transaction was proposed under when snapshotting the quorum", async () => {
+    const multisig = await dsl.createMultisig(3, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const contractor = Keypair.generate();
+
+    let setThresholdAgainstActiveInstruction = await program.methods
+      .setThresholdAgainstActive(true)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    let setSnapshotQuorumInstruction = await program.methods
+      .setSnapshotQuorum(true)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setThresholdAgainstActiveInstruction, setSnapshotQuorumInstruction]);
+    await addTemporaryOwner(multisig, contractor, new BN(Math.floor(Date.now() / 1000) + 3));
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    let transaction = await program.account.transaction.fetch(transactionAddress);
+    assert.ok(new BN(3).eq(transaction.requiredAtCreation));
+
+    await new Promise((resolve) => setTimeout(resolve, 6000));
+
+    assert.ok(new BN(3).eq(await dsl.effectiveThreshold(multisig.address, transactionAddress)), "Should keep the snapshotted threshold");
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    try {
+      await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NotEnoughSigners. Error Number: 6003. Error Message: Not enough owners signed this transaction"));
+    }
+
+    const laterTransactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    assert.ok(new BN(2).eq(await dsl.effectiveThreshold(multisig.address, laterTransactionAddress)), "Should snapshot the active owners at proposal");
+  }).timeout(40000);
+});
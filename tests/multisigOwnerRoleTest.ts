@@ -0,0 +1,114 @@
+import assert from "assert";
+import {setUpValidator} from "./utils/before";
+import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
+import {Keypair, PublicKey, SystemProgram} from "@solana/web3.js";
+import {MultisigAccount, MultisigDsl} from "./utils/multisigDsl";
+import {describe} from "mocha";
+import {fail} from "node:assert";
+
+const ROLE_TRANSFER = 1;
+const ROLE_GOVERNANCE = 2;
+
+describe("Test owner roles", async () => {
+  let provider: AnchorProvider;
+  let program: Program;
+  let dsl: MultisigDsl;
+
+  before(async () => {
+    let result = await setUpValidator(false);
+    program = result.program;
+    provider = result.provider;
+    dsl = new MultisigDsl(program, provider);
+  });
+
+  async function setOwnerRole(multisig: MultisigAccount, owner: PublicKey, role: number) {
+    let setOwnerRoleInstruction = await program.methods
+      .setOwnerRole(owner, role)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setOwnerRoleInstruction]);
+  }
+
+  it("should let every owner propose anything by default", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.ownerRoles, [255, 255, 255]);
+  }).timeout(30000);
+
+  it("should let a restricted owner propose a transaction in their category", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, ownerC] = multisig.owners;
+    await setOwnerRole(multisig, ownerC.publicKey, ROLE_TRANSFER);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerC, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerA, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerA, ownerC.publicKey);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.ownerRoles, [255, 255, ROLE_TRANSFER]);
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(30000);
+
+  it("should not let a restricted owner propose a transaction outside their category", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [_ownerA, _ownerB, ownerC] = multisig.owners;
+    await setOwnerRole(multisig, ownerC.publicKey, ROLE_TRANSFER);
+
+    let setOwnerRoleInstruction = await program.methods
+      .setOwnerRole(ownerC.publicKey, ROLE_TRANSFER | ROLE_GOVERNANCE)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    try {
+      await dsl.proposeTransaction(ownerC, [setOwnerRoleInstruction], multisig.address);
+      fail("Should have failed to propose transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InsufficientRole. Error Number: 6071. Error Message: The proposer's role doesn't cover every instruction of the transaction."));
+    }
+  }).timeout(30000);
+
+  it("should configure an owner's weight, role and expiry in one call", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [_ownerA, _ownerB, ownerC] = multisig.owners;
+    const expiresAt = new BN(Math.floor(Date.now() / 1000) + 600);
+
+    let configureOwnerInstruction = await program.methods
+      .configureOwner(ownerC.publicKey, {weight: new BN(2), role: ROLE_TRANSFER, expiresAt: expiresAt, removeExpiry: false})
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [configureOwnerInstruction]);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.weights.map(weight => weight.toNumber()), [1, 1, 2]);
+    assert.deepStrictEqual(actualMultisig.ownerRoles, [255, 255, ROLE_TRANSFER]);
+    assert.ok(expiresAt.eq(actualMultisig.ownerExpiries[2]));
+    assert.strictEqual(actualMultisig.ownerExpiries[0], null);
+  }).timeout(30000);
+
+  it("should not set the role of a key which isn't an owner", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+
+    try {
+      await setOwnerRole(multisig, Keypair.generate().publicKey, ROLE_TRANSFER);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidOwner. Error Number: 6000. Error Message: The given owner is not part of this multisig"));
+    }
+  }).timeout(30000);
+});
@@ -0,0 +1,83 @@
+import assert from "assert";
+import {setUpValidator} from "./utils/before";
+import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
+import {Keypair, PublicKey, SystemProgram, Transaction} from "@solana/web3.js";
+import {MultisigDsl} from "./utils/multisigDsl";
+import {describe} from "mocha";
+import {fail} from "node:assert";
+
+describe("Test executing with purpose signers", async () => {
+  let provider: AnchorProvider;
+  let program: Program;
+  let dsl: MultisigDsl;
+
+  before(async () => {
+    let result = await setUpValidator(false);
+    program = result.program;
+    provider = result.provider;
+    dsl = new MultisigDsl(program, provider);
+  });
+
+  async function fund(address: PublicKey, lamports: number) {
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.publicKey,
+          lamports: new BN(lamports),
+          toPubkey: address,
+        })
+      )
+    );
+  }
+
+  it("should sign with the purpose signer of the transaction", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const hotWallet = dsl.purposeSigner(multisig.address, 1);
+    const recipient = Keypair.generate().publicKey;
+    await fund(hotWallet, 2_000_000);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: hotWallet,
+      lamports: new BN(1_500_000),
+      toPubkey: recipient,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address, undefined, {purpose: 1});
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.strictEqual(transactionAccount.purpose, 1);
+
+    await dsl.executeTransactionWithMultipleInstructions(transactionAddress, [transactionInstruction], multisig.signer, multisig.address, ownerB, ownerA.publicKey, hotWallet);
+
+    await dsl.assertBalance(hotWallet, 500_000);
+    await dsl.assertBalance(recipient, 1_500_000);
+    await dsl.assertBalance(multisig.signer, 1_000_000);
+  }).timeout(20000);
+
+  it("should not sign for a purpose signer other than the one of the transaction", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const coldWallet = dsl.purposeSigner(multisig.address, 2);
+    await fund(coldWallet, 2_000_000);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: coldWallet,
+      lamports: new BN(1_500_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address, undefined, {purpose: 1});
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.executeTransactionWithMultipleInstructions(transactionAddress, [transactionInstruction], multisig.signer, multisig.address, ownerB, ownerA.publicKey, coldWallet);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.ok(e.logs.some((log: string) => log.includes("signer privilege escalated")));
+    }
+
+    await dsl.assertBalance(coldWallet, 2_000_000);
+  }).timeout(20000);
+});
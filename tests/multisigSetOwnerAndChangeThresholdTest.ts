@@ -283,4 +283,31 @@ describe("Test changing multisig owner and threshold atomically", async () => {
           new RegExp(".*Error Code: InvalidThreshold. Error Number: 6008. Error Message: Threshold must be less than or equal to the number of owners and greater than zero."));
     }
   });
+
+  it("should change neither owners nor threshold when only one of them is valid", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+
+    // Valid owners, and a threshold valid for the current owners, but not for the new ones
+    let changeTo1OwnerWithThresholdOf2 = await program.methods
+      .setOwnersAndChangeThreshold([ownerA.publicKey], new BN(2))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+
+    try {
+      await dsl.executeWithQuorum(multisig, [changeTo1OwnerWithThresholdOf2]);
+      fail("Should have not executed transaction");
+    } catch (e) {
+      assert.match(e.message,
+          new RegExp(".*Error Code: InvalidThreshold. Error Number: 6008.*"));
+    }
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.owners, [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey]);
+    assert.strictEqual(actualMultisig.threshold.toNumber(), 2);
+    assert.strictEqual(actualMultisig.ownerSetSeqno, 0);
+  }).timeout(20000);
 });
@@ -2,7 +2,7 @@ import assert = require("assert");
 import {setUpValidator} from "./utils/before";
 import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
 import {Keypair, PublicKey, SystemProgram,} from "@solana/web3.js";
-import {MultisigDsl} from "./utils/multisigDsl";
+import {MultisigAccount, MultisigDsl} from "./utils/multisigDsl";
 import {describe} from "mocha";
 import {fail} from "node:assert";
 
@@ -44,6 +44,29 @@ describe("Test changing multisig owner", async () => {
     assert.equal(actualMultisig.ownerSetSeqno, 1, "Should have incremented owner set seq number");
   });
 
+  it("should update owners hash when owners change", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const newOwners = [ownerA.publicKey, ownerB.publicKey, Keypair.generate().publicKey];
+    const hashBefore = (await program.account.multisig.fetch(multisig.address)).ownersHash;
+
+    let transactionInstruction = await program.methods
+      .setOwners(newOwners)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.notDeepStrictEqual(actualMultisig.ownersHash, hashBefore, "Should have changed owners hash");
+    assert.deepStrictEqual(actualMultisig.ownersHash, dsl.ownersHash(newOwners), "Should match hash of new owners");
+  });
+
   it("should allow re-expansion of owner list", async () => {
     const multisig = await dsl.createMultisig(2, 3);
     const [ownerA, ownerB, ownerC] = multisig.owners;
@@ -383,4 +406,681 @@ describe("Test changing multisig owner", async () => {
           new RegExp(".*Error Code: TooManyOwners. Error Number: 6002. Error Message: The number of owners cannot be increased."));
     }
   });
+
+  it("should keep the weights of owners carried over when owners change", async () => {
+    const [ownerA, ownerB, ownerC] = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const multisig = await dsl.createMultisigWithOwners(2, [ownerA, ownerB, ownerC], 0, 0, [2, 1, 1]);
+    const newOwner = Keypair.generate().publicKey;
+
+    let setOwnersInstruction = await program.methods
+      .setOwners([ownerC.publicKey, ownerA.publicKey, newOwner])
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setOwnersInstruction]);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.weights.map((weight: BN) => weight.toNumber()), [1, 2, 1]);
+  }).timeout(20000);
+
+  it("should close a decommissioned multisig and refund its rent", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const refundee = Keypair.generate().publicKey;
+    const multisigRent = (await provider.connection.getAccountInfo(multisig.address)).lamports;
+
+    let closeMultisigInstruction = await program.methods
+      .closeMultisig()
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+        refundee: refundee,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [closeMultisigInstruction]);
+
+    assert.strictEqual(await provider.connection.getAccountInfo(multisig.address, "confirmed"), null);
+    await dsl.assertBalance(refundee, multisigRent);
+  }).timeout(20000);
+
+  it("should remove a single owner", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+
+    let removeOwnerInstruction = await program.methods
+      .removeOwner(ownerB.publicKey)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [removeOwnerInstruction]);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.owners, [ownerA.publicKey, ownerC.publicKey]);
+    assert.ok(multisig.threshold.eq(actualMultisig.threshold));
+    assert.strictEqual(actualMultisig.ownerSetSeqno, 1);
+  }).timeout(20000);
+
+  it("should lower the threshold when removing an owner leaves fewer owners than it", async () => {
+    const multisig = await dsl.createMultisig(3, 3);
+    const [_ownerA, _ownerB, ownerC] = multisig.owners;
+
+    let removeOwnerInstruction = await program.methods
+      .removeOwner(ownerC.publicKey)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [removeOwnerInstruction]);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.strictEqual(actualMultisig.owners.length, 2);
+    assert.strictEqual(actualMultisig.threshold.toNumber(), 2);
+  }).timeout(20000);
+
+  it("should not remove an owner who isn't one", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+
+    let removeOwnerInstruction = await program.methods
+      .removeOwner(Keypair.generate().publicKey)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    try {
+      await dsl.executeWithQuorum(multisig, [removeOwnerInstruction]);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidOwner. Error Number: 6000. Error Message: The given owner is not part of this multisig"));
+    }
+  }).timeout(20000);
+
+  it("should record each owner set in the owner history", async () => {
+    const multisig = await dsl.createMultisig(2, 4);
+    const [ownerA, ownerB, ownerC, ownerD] = multisig.owners;
+
+    for (const owner of [ownerD, ownerC]) {
+      let removeOwnerInstruction = await program.methods
+        .removeOwner(owner.publicKey)
+        .accounts({
+          multisig: multisig.address,
+          multisigSigner: multisig.signer,
+        })
+        .instruction();
+      await dsl.executeWithQuorum(multisig, [removeOwnerInstruction]);
+    }
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.ownerHistory.map(record => record.ownerSetSeqno), [0, 1, 2]);
+    assert.deepStrictEqual(actualMultisig.ownerHistory.map(record => record.ownersHash), [
+      dsl.ownersHash([ownerA.publicKey, ownerB.publicKey, ownerC.publicKey, ownerD.publicKey]),
+      dsl.ownersHash([ownerA.publicKey, ownerB.publicKey, ownerC.publicKey]),
+      dsl.ownersHash([ownerA.publicKey, ownerB.publicKey]),
+    ]);
+    const changedAt = actualMultisig.ownerHistory.map(record => record.changedAt.toNumber());
+    assert.ok(changedAt[0] <= changedAt[1] && changedAt[1] <= changedAt[2]);
+  }).timeout(30000);
+
+  it("should grow the multisig account to add owners", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+    const newOwners = [Keypair.generate().publicKey, Keypair.generate().publicKey];
+    const accountBefore = await provider.connection.getAccountInfo(multisig.address);
+
+    let addOwnersInstruction = await program.methods
+      .addOwners(newOwners)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+        payer: provider.publicKey,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [addOwnersInstruction]);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.owners, [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey, ...newOwners]);
+    assert.ok(multisig.threshold.eq(actualMultisig.threshold));
+    assert.strictEqual(actualMultisig.ownerSetSeqno, 1);
+
+    const accountAfter = await provider.connection.getAccountInfo(multisig.address);
+    assert.ok(accountAfter.data.length > accountBefore.data.length, "Should have grown the account");
+    assert.strictEqual(accountAfter.lamports, await provider.connection.getMinimumBalanceForRentExemption(accountAfter.data.length),
+      "Should have topped up rent for the larger account");
+  }).timeout(20000);
+
+  it("should not allow owners to be changed again within the cooldown", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    async function setOwnersInstruction(owners: Array<PublicKey>) {
+      return await program.methods
+        .setOwners(owners)
+        .accounts({
+          multisig: multisig.address,
+          multisigSigner: multisig.signer,
+        })
+        .instruction();
+    }
+
+    let setOwnerChangeCooldownInstruction = await program.methods
+      .setOwnerChangeCooldown(new BN(5))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setOwnerChangeCooldownInstruction]);
+
+    await dsl.executeWithQuorum(multisig, [await setOwnersInstruction([ownerA.publicKey, ownerB.publicKey, Keypair.generate().publicKey])]);
+
+    const latestOwners = [ownerA.publicKey, ownerB.publicKey, Keypair.generate().publicKey];
+    try {
+      await dsl.executeWithQuorum(multisig, [await setOwnersInstruction(latestOwners)]);
+      fail("Should have not executed transaction");
+    } catch (e) {
+      assert.match(e.message,
+          new RegExp(".*Error Code: OwnerChangeCooldown. Error Number: 6020. Error Message: Owners were changed too recently."));
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 8000));
+    await dsl.executeWithQuorum(multisig, [await setOwnersInstruction(latestOwners)]);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.owners, latestOwners);
+    assert.strictEqual(actualMultisig.ownerSetSeqno, 2);
+  }).timeout(40000);
+
+  it("should not allow a second owner change proposal while one is open when capped", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    async function setOwnersInstruction(owners: Array<PublicKey>) {
+      return await program.methods
+        .setOwners(owners)
+        .accounts({
+          multisig: multisig.address,
+          multisigSigner: multisig.signer,
+        })
+        .instruction();
+    }
+
+    let setMaxOpenOwnerChangesInstruction = await program.methods
+      .setMaxOpenOwnerChanges(1)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setMaxOpenOwnerChangesInstruction]);
+
+    const firstProposal = await dsl.proposeTransaction(ownerA, [await setOwnersInstruction([ownerA.publicKey, ownerB.publicKey, Keypair.generate().publicKey])], multisig.address);
+    assert.strictEqual((await program.account.multisig.fetch(multisig.address)).openOwnerChanges, 1);
+
+    const latestOwners = [ownerA.publicKey, ownerB.publicKey, Keypair.generate().publicKey];
+    try {
+      await dsl.proposeTransaction(ownerB, [await setOwnersInstruction(latestOwners)], multisig.address);
+      fail("Should have failed to propose transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: OwnerChangeInProgress. Error Number: 6048. Error Message: Another proposal changing the owners is already open."));
+    }
+
+    await dsl.cancelTransaction(firstProposal, multisig.address, ownerA, ownerA.publicKey);
+    assert.strictEqual((await program.account.multisig.fetch(multisig.address)).openOwnerChanges, 0);
+
+    await dsl.executeWithQuorum(multisig, [await setOwnersInstruction(latestOwners)]);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.owners, latestOwners);
+    assert.strictEqual(actualMultisig.openOwnerChanges, 0);
+  }).timeout(40000);
+
+  it("should not let a new owner approve until the new owner delay has passed", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const newOwner = Keypair.generate();
+
+    let setNewOwnerDelayInstruction = await program.methods
+      .setNewOwnerDelay(new BN(5))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    let setOwnersInstruction = await program.methods
+      .setOwners([ownerA.publicKey, ownerB.publicKey, newOwner.publicKey])
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setNewOwnerDelayInstruction, setOwnersInstruction]);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.ok(actualMultisig.ownerAddedAt[0].eqn(0), "Should keep the founding owner's added time");
+    assert.ok(actualMultisig.ownerAddedAt[2].gtn(0), "Should record when the new owner was added");
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    try {
+      await dsl.approveTransaction(newOwner, multisig.address, transactionAddress);
+      fail("Should have failed to approve transaction");
+    } catch (e) {
+      assert.match(e.message,
+          new RegExp(".*Error Code: NewOwnerCoolingOff. Error Number: 6034. Error Message: The owner was added too recently to approve."));
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 8000));
+    await dsl.approveTransaction(newOwner, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(40000);
+
+  it("should execute a transaction approved by the previous owners within the churn grace period", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let setChurnGraceSecsInstruction = await program.methods
+      .setChurnGraceSecs(new BN(60))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setChurnGraceSecsInstruction]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.strictEqual(transactionAccount.reachedThreshold, true);
+
+    let setOwnersInstruction = await program.methods
+      .setOwners([ownerA.publicKey, ownerB.publicKey, Keypair.generate().publicKey])
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setOwnersInstruction]);
+
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(40000);
+
+  it("should carry pending transactions over when reordering owners", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    let reorderOwnersInstruction = await program.methods
+      .reorderOwners(Buffer.from([2, 0, 1]))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .remainingAccounts([{pubkey: transactionAddress, isWritable: true, isSigner: false}])
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [reorderOwnersInstruction]);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.owners, [ownerC.publicKey, ownerA.publicKey, ownerB.publicKey]);
+    assert.strictEqual(actualMultisig.ownerSetSeqno, 1);
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.strictEqual(transactionAccount.ownerSetSeqno, 1);
+    assert.deepStrictEqual(transactionAccount.signers, [false, true, false]);
+
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+    const roster = await dsl.approvalRoster(multisig.address, transactionAddress);
+    assert.deepStrictEqual(roster.map(entry => entry.approved), [false, true, true]);
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(40000);
+
+  it("should restore owners and threshold saved in a profile", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+    const profileAddress = dsl.profileAddress(multisig.address, "full-board");
+
+    let saveProfileInstruction = await program.methods
+      .saveProfile("full-board")
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+        profile: profileAddress,
+        payer: provider.publicKey,
+      })
+      .instruction();
+    let setOwnersAndChangeThresholdInstruction = await program.methods
+      .setOwnersAndChangeThreshold([ownerA.publicKey, ownerB.publicKey], new BN(1))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [saveProfileInstruction, setOwnersAndChangeThresholdInstruction]);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.owners, [ownerA.publicKey, ownerB.publicKey]);
+    assert.ok(new BN(1).eq(actualMultisig.threshold));
+
+    let restoreProfileInstruction = await program.methods
+      .restoreProfile()
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+        profile: profileAddress,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [restoreProfileInstruction]);
+
+    actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.owners, [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey]);
+    assert.ok(new BN(2).eq(actualMultisig.threshold));
+    assert.strictEqual(actualMultisig.ownerSetSeqno, 2);
+  }).timeout(40000);
+
+  async function repairSignersInstruction(multisig: MultisigAccount, transaction: PublicKey) {
+    return await program.methods
+      .repairSigners()
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+        transaction: transaction,
+        payer: provider.publicKey,
+      })
+      .instruction();
+  }
+
+  it("should keep approvals when repairing signers of a transaction for the current owners", async () => {
+    const multisig = await dsl.createMultisig(3, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    await dsl.executeWithQuorum(multisig, [await repairSignersInstruction(multisig, transactionAddress)]);
+
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.signers, [true, true, false]);
+  });
+
+  it("should clear approvals when repairing signers of a transaction for previous owners", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    let setOwnersInstruction = await program.methods
+      .setOwners([ownerA.publicKey, ownerB.publicKey])
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setOwnersInstruction]);
+
+    await dsl.executeWithQuorum(multisig, [await repairSignersInstruction(multisig, transactionAddress)]);
+
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.signers, [false, false]);
+  });
+
+  async function proposeOwners(multisig: MultisigAccount, owners: Array<PublicKey>, expiresAt: BN = new BN(Math.floor(Date.now() / 1000) + 600)) {
+    let proposeOwnersInstruction = await program.methods
+      .proposeOwners(owners, expiresAt)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [proposeOwnersInstruction]);
+  }
+
+  it("should not change owners until every proposed owner has accepted", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+    const [newOwnerA, newOwnerB] = [Keypair.generate(), Keypair.generate()];
+    const newOwners = [ownerA.publicKey, newOwnerA.publicKey, newOwnerB.publicKey];
+    await proposeOwners(multisig, newOwners);
+
+    await dsl.acceptOwnership(multisig.address, ownerA);
+    await dsl.acceptOwnership(multisig.address, newOwnerA);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.owners, [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey], "Should not have changed owners yet");
+    assert.deepStrictEqual(actualMultisig.pendingOwners, newOwners);
+    assert.deepStrictEqual(actualMultisig.pendingOwnerAccepted, [true, true, false]);
+    assert.strictEqual(actualMultisig.ownerSetSeqno, 0);
+  });
+
+  it("should change owners once every proposed owner has accepted", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    const [newOwnerA, newOwnerB] = [Keypair.generate(), Keypair.generate()];
+    const newOwners = [ownerA.publicKey, newOwnerA.publicKey, newOwnerB.publicKey];
+    await proposeOwners(multisig, newOwners);
+
+    for (const owner of [newOwnerB, ownerA, newOwnerA]) {
+      await dsl.acceptOwnership(multisig.address, owner);
+    }
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.owners, newOwners, "Should have updated to new owners");
+    assert.strictEqual(actualMultisig.ownerSetSeqno, 1, "Should have incremented owner set seq number");
+    assert.deepStrictEqual(actualMultisig.pendingOwners, []);
+    assert.deepStrictEqual(actualMultisig.pendingOwnerAccepted, []);
+  });
+
+  it("should not let anyone but a proposed owner accept ownership", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [_ownerA, _ownerB, ownerC] = multisig.owners;
+    await proposeOwners(multisig, [Keypair.generate().publicKey, Keypair.generate().publicKey]);
+
+    try {
+      await dsl.acceptOwnership(multisig.address, ownerC);
+      fail("Should have failed to accept ownership");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NotAPendingOwner. Error Number: 6064. Error Message: The signer is not one of the proposed owners."));
+    }
+  });
+
+  it("should not let proposed owners accept once the proposal has expired", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+    const newOwner = Keypair.generate();
+    await proposeOwners(multisig, [ownerA.publicKey, newOwner.publicKey], new BN(Math.floor(Date.now() / 1000) + 2));
+    await dsl.acceptOwnership(multisig.address, ownerA);
+
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+    try {
+      await dsl.acceptOwnership(multisig.address, newOwner);
+      fail("Should have failed to accept ownership");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: PendingOwnersExpired. Error Number: 6072. Error Message: The proposed owners have expired."));
+    }
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.owners, [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey]);
+  }).timeout(30000);
+
+  it("should grow a multisig to 50 owners within the compute budget", async () => {
+    const multisig = await dsl.createMultisig(1, 20);
+    for (let batch = 0; batch < 3; batch++) {
+      let addOwnersInstruction = await program.methods
+        .addOwners(Array.from({length: 10}, () => Keypair.generate().publicKey))
+        .accounts({
+          multisig: multisig.address,
+          multisigSigner: multisig.signer,
+          payer: provider.publicKey,
+        })
+        .instruction();
+      await dsl.executeWithQuorum(multisig, [addOwnersInstruction]);
+    }
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.strictEqual(actualMultisig.owners.length, 50);
+    assert.strictEqual(new Set(actualMultisig.owners.map((owner: PublicKey) => owner.toBase58())).size, 50);
+  }).timeout(60000);
+
+  async function replaceOwner(multisig: MultisigAccount, oldOwner: PublicKey, newOwner: PublicKey) {
+    let replaceOwnerInstruction = await program.methods
+      .replaceOwner(oldOwner, newOwner)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [replaceOwnerInstruction]);
+  }
+
+  it("should replace a single owner in place, keeping their weight", async () => {
+    const [ownerA, ownerB, ownerC] = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const multisig = await dsl.createMultisigWithOwners(2, [ownerA, ownerB, ownerC], 0, 0, [1, 2, 1]);
+    const newOwner = Keypair.generate().publicKey;
+
+    await replaceOwner(multisig, ownerB.publicKey, newOwner);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.owners, [ownerA.publicKey, newOwner, ownerC.publicKey]);
+    assert.deepStrictEqual(actualMultisig.weights.map((weight: BN) => weight.toNumber()), [1, 2, 1]);
+    assert.ok(actualMultisig.threshold.eq(new BN(2)));
+    assert.strictEqual(actualMultisig.ownerSetSeqno, 1);
+  }).timeout(20000);
+
+  it("should not replace an owner who isn't one", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+
+    try {
+      await replaceOwner(multisig, Keypair.generate().publicKey, Keypair.generate().publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidOwner. Error Number: 6000. Error Message: The given owner is not part of this multisig"));
+    }
+  }).timeout(20000);
+
+  it("should not replace an owner with one who already is", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, _ownerB, ownerC] = multisig.owners;
+
+    try {
+      await replaceOwner(multisig, ownerA.publicKey, ownerC.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: UniqueOwners. Error Number: 6009. Error Message: Owners must be unique."));
+    }
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.strictEqual(actualMultisig.ownerSetSeqno, 0);
+  }).timeout(20000);
+
+  it("should require the admin threshold to replace an owner", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+    const newOwner = Keypair.generate().publicKey;
+
+    let setAdminThreshold = await program.methods
+      .setAdminThreshold(new BN(3))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setAdminThreshold]);
+
+    let replaceOwnerInstruction = await program.methods
+      .replaceOwner(ownerC.publicKey, newOwner)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [replaceOwnerInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.executeTransaction(transactionAddress, replaceOwnerInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NotEnoughSigners. Error Number: 6003. Error Message: Not enough owners signed this transaction"));
+    }
+
+    await dsl.approveTransaction(ownerC, multisig.address, transactionAddress);
+    await dsl.executeTransaction(transactionAddress, replaceOwnerInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.deepStrictEqual(actualMultisig.owners, [ownerA.publicKey, ownerB.publicKey, newOwner]);
+  }).timeout(30000);
+
+  it("should not let a replacement key approve until the new owner delay has passed", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, ownerC] = multisig.owners;
+    const newOwner = Keypair.generate();
+
+    let setNewOwnerDelayInstruction = await program.methods
+      .setNewOwnerDelay(new BN(60))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setNewOwnerDelayInstruction]);
+    await replaceOwner(multisig, ownerC.publicKey, newOwner.publicKey);
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.ok(actualMultisig.ownerAddedAt[2].gtn(0), "Should record when the replacement key was added");
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    try {
+      await dsl.approveTransaction(newOwner, multisig.address, transactionAddress);
+      fail("Should have failed to approve transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NewOwnerCoolingOff. Error Number: 6034. Error Message: The owner was added too recently to approve."));
+    }
+  }).timeout(30000);
 });
@@ -0,0 +1,259 @@
+import assert from "assert";
+import {setUpValidator} from "./utils/before";
+import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
+import {AccountMeta, Keypair, PublicKey, SystemProgram} from "@solana/web3.js";
+import {mintToChecked} from "@solana/spl-token";
+import {MultisigDsl} from "./utils/multisigDsl";
+import {describe} from "mocha";
+import {fail} from "node:assert";
+
+describe("Test stake weighted approvals", async () => {
+  let provider: AnchorProvider;
+  let program: Program;
+  let dsl: MultisigDsl;
+
+  before(async () => {
+    let result = await setUpValidator(false);
+    program = result.program;
+    provider = result.provider;
+    dsl = new MultisigDsl(program, provider);
+  });
+
+  function readOnly(accounts: Array<PublicKey>): Array<AccountMeta> {
+    return accounts.map(account => ({pubkey: account, isWritable: false, isSigner: false}));
+  }
+
+  it("should weigh approvals by the live balance of each owner's stake account", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+    const mint = await dsl.createTokenMint(0);
+    const [stakeA, stakeB, stakeC] = await Promise.all([
+      dsl.createAta(mint, ownerA.publicKey, 60),
+      dsl.createAta(mint, ownerB.publicKey, 30),
+      dsl.createAta(mint, ownerC.publicKey, 50),
+    ]);
+    const stakeAccounts = [stakeA.address, stakeB.address, stakeC.address];
+
+    let setStakeWeightingInstruction = await program.methods
+      .setStakeWeighting(stakeAccounts, new BN(100), new BN(0))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setStakeWeightingInstruction]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    // A well funded token account standing in for ownerB's doesn't count.
+    const impostor = await dsl.createAta(mint, Keypair.generate().publicKey, 1_000);
+    try {
+      await dsl.executeTransactionWithMultipleInstructions(transactionAddress, [transactionInstruction], multisig.signer, multisig.address, ownerA, ownerA.publicKey, undefined, [], readOnly([stakeA.address, impostor.address]));
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidStakeAccount. Error Number: 6039. Error Message: A stake account is missing or not a token account."));
+    }
+
+    // Two of three owners approved, but with only 90 of the 100 stake needed.
+    try {
+      await dsl.executeTransactionWithMultipleInstructions(transactionAddress, [transactionInstruction], multisig.signer, multisig.address, ownerA, ownerA.publicKey, undefined, [], readOnly(stakeAccounts));
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NotEnoughSigners. Error Number: 6003. Error Message: Not enough owners signed this transaction"));
+    }
+
+    await mintToChecked(provider.connection, mint.owner, mint.account, stakeB.address, mint.owner.publicKey, 10, mint.decimals);
+    await dsl.executeTransactionWithMultipleInstructions(transactionAddress, [transactionInstruction], multisig.signer, multisig.address, ownerA, ownerA.publicKey, undefined, [], readOnly(stakeAccounts));
+
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(40000);
+
+  it("should approve and execute a stake weighted transaction in one call", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+    const mint = await dsl.createTokenMint(0);
+    const [stakeA, stakeB, stakeC] = await Promise.all([
+      dsl.createAta(mint, ownerA.publicKey, 60),
+      dsl.createAta(mint, ownerB.publicKey, 40),
+      dsl.createAta(mint, ownerC.publicKey, 50),
+    ]);
+    const stakeAccounts = [stakeA.address, stakeB.address, stakeC.address];
+
+    let setStakeWeightingInstruction = await program.methods
+      .setStakeWeighting(stakeAccounts, new BN(100), new BN(0))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setStakeWeightingInstruction]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveAndExecute(ownerB, multisig.address, multisig.signer, transactionAddress, [transactionInstruction], ownerA.publicKey, readOnly(stakeAccounts));
+
+    await dsl.assertBalance(multisig.signer, 900_000);
+    assert.strictEqual(await provider.connection.getAccountInfo(transactionAddress, "confirmed"), null);
+  }).timeout(40000);
+
+  it("should execute a stake weighted transaction approved by the previous owners within the churn grace period", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+    const mint = await dsl.createTokenMint(0);
+    const [stakeA, stakeB, stakeC] = await Promise.all([
+      dsl.createAta(mint, ownerA.publicKey, 60),
+      dsl.createAta(mint, ownerB.publicKey, 40),
+      dsl.createAta(mint, ownerC.publicKey, 50),
+    ]);
+    const stakeAccounts = [stakeA.address, stakeB.address, stakeC.address];
+
+    let setChurnGraceSecsInstruction = await program.methods
+      .setChurnGraceSecs(new BN(60))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    let setStakeWeightingInstruction = await program.methods
+      .setStakeWeighting(stakeAccounts, new BN(100), new BN(0))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setChurnGraceSecsInstruction, setStakeWeightingInstruction]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress, readOnly(stakeAccounts));
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.strictEqual(transactionAccount.reachedThreshold, true);
+
+    let setOwnersInstruction = await program.methods
+      .setOwners([ownerA.publicKey, ownerB.publicKey, Keypair.generate().publicKey])
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    const setOwnersAddress: PublicKey = await dsl.proposeTransaction(ownerA, [setOwnersInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, setOwnersAddress, readOnly(stakeAccounts));
+    await dsl.executeTransactionWithMultipleInstructions(setOwnersAddress, [setOwnersInstruction], multisig.signer, multisig.address, ownerA, ownerA.publicKey, undefined, [], readOnly(stakeAccounts));
+
+    await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+
+    await dsl.assertBalance(multisig.signer, 900_000);
+  }).timeout(40000);
+
+  it("should require the admin stake threshold for admin changes", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, ownerC] = multisig.owners;
+    const mint = await dsl.createTokenMint(0);
+    const [stakeA, stakeB, stakeC] = await Promise.all([
+      dsl.createAta(mint, ownerA.publicKey, 60),
+      dsl.createAta(mint, ownerB.publicKey, 40),
+      dsl.createAta(mint, ownerC.publicKey, 50),
+    ]);
+    const stakeAccounts = [stakeA.address, stakeB.address, stakeC.address];
+
+    let setStakeWeightingInstruction = await program.methods
+      .setStakeWeighting(stakeAccounts, new BN(100), new BN(150))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setStakeWeightingInstruction]);
+
+    let setReadThresholdInstruction = await program.methods
+      .setReadThreshold(new BN(1))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [setReadThresholdInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    // The 100 stake of ownerA and ownerB meets the stake threshold, but not the admin stake threshold.
+    try {
+      await dsl.executeTransactionWithMultipleInstructions(transactionAddress, [setReadThresholdInstruction], multisig.signer, multisig.address, ownerA, ownerA.publicKey, undefined, [], readOnly(stakeAccounts));
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: NotEnoughSigners. Error Number: 6003. Error Message: Not enough owners signed this transaction"));
+    }
+
+    await dsl.approveTransaction(ownerC, multisig.address, transactionAddress);
+    await dsl.executeTransactionWithMultipleInstructions(transactionAddress, [setReadThresholdInstruction], multisig.signer, multisig.address, ownerA, ownerA.publicKey, undefined, [], readOnly(stakeAccounts));
+
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.ok(new BN(1).eq(actualMultisig.readThreshold));
+  }).timeout(40000);
+
+  it("should not weigh approvals by stake without an admin stake threshold when there is an admin threshold", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+
+    let setAdminThresholdInstruction = await program.methods
+      .setAdminThreshold(new BN(3))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    let setStakeWeightingInstruction = await program.methods
+      .setStakeWeighting(multisig.owners.map(_owner => Keypair.generate().publicKey), new BN(100), new BN(0))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+
+    try {
+      await dsl.executeWithQuorum(multisig, [setAdminThresholdInstruction, setStakeWeightingInstruction]);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidAdminStakeThreshold. Error Number: 6077. Error Message: The admin stake threshold must be at least the stake threshold, and set whenever the admin threshold is."));
+    }
+  }).timeout(20000);
+
+  it("should require one stake account per owner", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let setStakeWeightingInstruction = await program.methods
+      .setStakeWeighting([Keypair.generate().publicKey, Keypair.generate().publicKey], new BN(100), new BN(0))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [setStakeWeightingInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      await dsl.executeTransaction(transactionAddress, setStakeWeightingInstruction, multisig.signer, multisig.address, ownerA, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidStakeWeighting. Error Number: 6038. Error Message: Stake weighting needs one stake account per owner and a positive stake threshold."));
+    }
+  }).timeout(20000);
+});
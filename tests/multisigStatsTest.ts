@@ -0,0 +1,65 @@
+import assert from "assert";
+import {setUpValidator} from "./utils/before";
+import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
+import {PublicKey, SystemProgram} from "@solana/web3.js";
+import {MultisigDsl} from "./utils/multisigDsl";
+import {describe} from "mocha";
+
+describe("Test stats account", async () => {
+  let provider: AnchorProvider;
+  let program: Program;
+  let dsl: MultisigDsl;
+
+  before(async () => {
+    let result = await setUpValidator(false);
+    program = result.program;
+    provider = result.provider;
+    dsl = new MultisigDsl(program, provider);
+  });
+
+  it("should count executed and cancelled transactions", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const stats = await dsl.createStats(multisig.address);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    for (let i = 0; i < 2; i++) {
+      const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+      await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+      await dsl.executeTransaction(transactionAddress, transactionInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+    }
+    const cancelledAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.cancelTransaction(cancelledAddress, multisig.address, ownerB, ownerA.publicKey);
+
+    let statsAccount = await program.account.stats.fetch(stats);
+    assert.ok(statsAccount.multisig.equals(multisig.address));
+    assert.strictEqual(statsAccount.transactionsCreated.toNumber(), 3);
+    assert.strictEqual(statsAccount.transactionsExecuted.toNumber(), 2);
+    assert.strictEqual(statsAccount.transactionsCancelled.toNumber(), 1);
+    assert.strictEqual(statsAccount.transactionsExpired.toNumber(), 0);
+  }).timeout(20000);
+
+  it("should count deleted transactions as cancelled", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    const stats = await dsl.createStats(multisig.address);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const transactionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    await dsl.deleteTransaction(transactionAddress, multisig.address, ownerA, ownerA.publicKey);
+
+    let statsAccount = await program.account.stats.fetch(stats);
+    assert.strictEqual(statsAccount.transactionsCreated.toNumber(), 1);
+    assert.strictEqual(statsAccount.transactionsCancelled.toNumber(), 1);
+  }).timeout(20000);
+});
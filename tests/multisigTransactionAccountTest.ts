@@ -1,7 +1,7 @@
 import assert = require("assert");
 import {setUpValidator} from "./utils/before";
 import {AnchorProvider, BN, Program} from "@coral-xyz/anchor";
-import {Keypair, PublicKey, SystemProgram, Transaction,} from "@solana/web3.js";
+import {Keypair, PublicKey, SystemProgram, Transaction, TransactionInstruction,} from "@solana/web3.js";
 import {MultisigDsl} from "./utils/multisigDsl";
 import {describe} from "mocha";
 
@@ -145,6 +145,240 @@ describe("Test transaction accounts", async () => {
     }
   });
 
+  it("should flag a bundle whose instructions expect different signers when requested", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    const extraSigner = Keypair.generate();
+
+    let transferFromMultisig = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    let transferFromExtraSigner = SystemProgram.transfer({
+      fromPubkey: extraSigner.publicKey,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    try {
+      await dsl.proposeTransaction(ownerA, [transferFromMultisig, transferFromExtraSigner], multisig.address, undefined, {requireConsistentSigners: true});
+      fail("Should have failed to propose transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InconsistentSigners. Error Number: 6018. Error Message: Instructions expect different external signers."));
+    }
+
+    // Without the validation option the same bundle can still be proposed.
+    await dsl.proposeTransaction(ownerA, [transferFromMultisig, transferFromExtraSigner], multisig.address);
+  });
+
+  it("should accept a bundle whose instructions expect the same signers when requested", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+
+    let transfer1 = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    let transfer2 = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(200_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const transactionAddress = await dsl.proposeTransaction(ownerA, [transfer1, transfer2], multisig.address, undefined, {requireConsistentSigners: true});
+
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.strictEqual(transactionAccount.instructions.length, 2);
+  });
+
+  it("should require a payer distinct from the proposer when configured", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+
+    let setRequireDistinctPayerProposerInstruction = await program.methods
+      .setRequireDistinctPayerProposer(true)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setRequireDistinctPayerProposerInstruction]);
+
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: provider.publicKey,
+          lamports: new BN(1_000_000_000),
+          toPubkey: ownerA.publicKey,
+        })
+      )
+    );
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    try {
+      await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address, undefined, {payer: ownerA});
+      fail("Should have failed to propose transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: PayerMustDiffer. Error Number: 6022. Error Message: The payer must differ from the proposer."));
+    }
+
+    const transactionAddress = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.signers, [true, false, false]);
+  });
+
+  it("should require a memo when configured", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+
+    let setRequireMemoInstruction = await program.methods
+      .setRequireMemo(true)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setRequireMemoInstruction]);
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    try {
+      await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+      fail("Should have failed to propose transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: MemoRequired. Error Number: 6032. Error Message: A memo is required."));
+    }
+
+    const transactionAddress = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address, undefined, {memo: "Pay invoice #42"});
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.strictEqual(transactionAccount.memo, "Pay invoice #42");
+  });
+
+  it("should not create a transaction with a memo longer than the maximum", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+
+    const longestMemo = "x".repeat(200);
+    const transactionAddress = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address, undefined, {memo: longestMemo});
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.strictEqual(transactionAccount.memo, longestMemo);
+
+    try {
+      await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address, undefined, {memo: longestMemo + "x"});
+      fail("Should have failed to propose transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: MemoTooLong. Error Number: 6057. Error Message: The memo is longer than the maximum allowed."));
+    }
+  });
+
+  it("should limit the number of instructions of a transaction when configured", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+
+    let setInstructionLimitsInstruction = await program.methods
+      .setInstructionLimits(2, 0)
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+      })
+      .instruction();
+    await dsl.executeWithQuorum(multisig, [setInstructionLimitsInstruction]);
+
+    const instructions = [0, 1, 2].map(() => SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    }));
+    await dsl.proposeTransaction(ownerA, instructions.slice(0, 2), multisig.address);
+
+    try {
+      await dsl.proposeTransaction(ownerA, instructions, multisig.address);
+      fail("Should have failed to propose transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: TooManyInstructions. Error Number: 6062. Error Message: The transaction has more instructions than the multisig allows."));
+    }
+  });
+
+  it("should limit the data of each instruction of a transaction when configured", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const setInstructionLimits = async (maxDataLen: number) => {
+      let setInstructionLimitsInstruction = await program.methods
+        .setInstructionLimits(0, maxDataLen)
+        .accounts({
+          multisig: multisig.address,
+          multisigSigner: multisig.signer,
+        })
+        .instruction();
+      await dsl.executeWithQuorum(multisig, [setInstructionLimitsInstruction]);
+    };
+
+    await setInstructionLimits(transactionInstruction.data.length);
+    await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+
+    await setInstructionLimits(transactionInstruction.data.length - 1);
+    try {
+      await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+      fail("Should have failed to propose transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InstructionDataTooLarge. Error Number: 6063. Error Message: An instruction has more data than the multisig allows."));
+    }
+  });
+
+  it("should store a display order independent of the execution order", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    const [recipientA, recipientB] = [Keypair.generate().publicKey, Keypair.generate().publicKey];
+
+    let instructions = [recipientA, recipientB].map(recipient => SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: recipient,
+    }));
+
+    try {
+      await dsl.proposeTransaction(ownerA, instructions, multisig.address, undefined, {displayOrder: [0, 0]});
+      fail("Should have failed to propose transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: InvalidDisplayOrder. Error Number: 6035. Error Message: The display order must list each instruction index exactly once."));
+    }
+
+    const transactionAddress = await dsl.proposeTransaction(ownerA, instructions, multisig.address, undefined, {displayOrder: [1, 0]});
+    let transactionAccount = await program.account.transaction.fetch(transactionAddress);
+    assert.deepStrictEqual(transactionAccount.displayOrder, [1, 0]);
+    assert.deepStrictEqual(transactionAccount.instructions.map(ix => ix.accounts[1].pubkey), [recipientA, recipientB]);
+  });
+
   it("should not be able to edit transaction account with transaction account private key after initialisation", async () => {
     const multisig = await dsl.createMultisig(2, 3);
 
@@ -235,4 +469,51 @@ describe("Test transaction accounts", async () => {
     }
   });
 
+  it("should number transactions in the order they were created", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    let transactionInstruction = SystemProgram.transfer({
+      fromPubkey: multisig.signer,
+      lamports: new BN(100_000),
+      toPubkey: provider.publicKey,
+    });
+    const firstAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    const secondAddress: PublicKey = await dsl.proposeTransaction(ownerB, [transactionInstruction], multisig.address);
+
+    const first = await program.account.transaction.fetch(firstAddress);
+    const second = await program.account.transaction.fetch(secondAddress);
+    assert.strictEqual(second.seq.toNumber(), first.seq.toNumber() + 1);
+
+    // The count carries on past transactions which are no longer around.
+    await dsl.cancelTransaction(secondAddress, multisig.address, ownerB, ownerB.publicKey);
+    const thirdAddress: PublicKey = await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+    const third = await program.account.transaction.fetch(thirdAddress);
+    assert.strictEqual(third.seq.toNumber(), second.seq.toNumber() + 1);
+    let actualMultisig = await program.account.multisig.fetch(multisig.address);
+    assert.strictEqual(actualMultisig.transactionCount.toNumber(), third.seq.toNumber() + 1);
+  });
+
+  it("should not create a transaction listing an account twice with different flags", async () => {
+    const multisig = await dsl.createMultisig(2, 3, 1_000_000);
+    const [ownerA, _ownerB, _ownerC] = multisig.owners;
+    const account = Keypair.generate().publicKey;
+
+    let transactionInstruction = new TransactionInstruction({
+      programId: SystemProgram.programId,
+      keys: [
+        {pubkey: account, isSigner: false, isWritable: false},
+        {pubkey: account, isSigner: false, isWritable: true},
+      ],
+      data: Buffer.alloc(0),
+    });
+    try {
+      await dsl.proposeTransaction(ownerA, [transactionInstruction], multisig.address);
+      fail("Should have failed to propose transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: DuplicateAccountMeta. Error Number: 6070. Error Message: An instruction lists the same account more than once with different flags."));
+    }
+  });
+
 });
@@ -0,0 +1,108 @@
+import assert from "assert";
+import {MOCK_UPGRADEABLE_PROGRAM, setUpValidator} from "./utils/before";
+import {AnchorProvider, Program} from "@coral-xyz/anchor";
+import {Keypair, PublicKey, Transaction, TransactionInstruction} from "@solana/web3.js";
+import {MultisigDsl} from "./utils/multisigDsl";
+import {describe} from "mocha";
+import {fail} from "node:assert";
+
+describe("Test upgrade authority handoff", async () => {
+  let provider: AnchorProvider;
+  let program: Program;
+  let dsl: MultisigDsl;
+
+  before(async () => {
+    let result = await setUpValidator(false);
+    program = result.program;
+    provider = result.provider;
+    dsl = new MultisigDsl(program, provider);
+  });
+
+  it("should not hand over upgrade authority the multisig does not hold", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const newAuthority = Keypair.generate().publicKey;
+
+    const transactionAddress: PublicKey = await dsl.proposeUpgradeAuthorityHandoff(ownerA, multisig, MOCK_UPGRADEABLE_PROGRAM, newAuthority);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    try {
+      const handoff = dsl.setUpgradeAuthorityInstruction(MOCK_UPGRADEABLE_PROGRAM, multisig.signer, newAuthority);
+      await dsl.executeTransaction(transactionAddress, handoff, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.ok(e.message.includes("The multisig signer is not the current upgrade authority of the program."));
+    }
+
+    await dsl.assertUpgradeAuthority(MOCK_UPGRADEABLE_PROGRAM, provider.publicKey);
+  }).timeout(20000);
+
+  it("should hand over upgrade authority held by the multisig", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+    const newAuthority = Keypair.generate();
+
+    await provider.sendAndConfirm(
+      new Transaction().add(dsl.setUpgradeAuthorityInstruction(MOCK_UPGRADEABLE_PROGRAM, provider.publicKey, multisig.signer))
+    );
+    await dsl.assertUpgradeAuthority(MOCK_UPGRADEABLE_PROGRAM, multisig.signer);
+
+    const transactionAddress: PublicKey = await dsl.proposeUpgradeAuthorityHandoff(ownerA, multisig, MOCK_UPGRADEABLE_PROGRAM, newAuthority.publicKey);
+    await dsl.approveTransaction(ownerB, multisig.address, transactionAddress);
+
+    const handoff = dsl.setUpgradeAuthorityInstruction(MOCK_UPGRADEABLE_PROGRAM, multisig.signer, newAuthority.publicKey);
+    await dsl.executeTransaction(transactionAddress, handoff, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+
+    await dsl.assertUpgradeAuthority(MOCK_UPGRADEABLE_PROGRAM, newAuthority.publicKey);
+
+    // Hand the mock program back to the wallet for the other tests in this file.
+    await provider.sendAndConfirm(
+      new Transaction().add(dsl.setUpgradeAuthorityInstruction(MOCK_UPGRADEABLE_PROGRAM, newAuthority.publicKey, provider.publicKey)),
+      [newAuthority]
+    );
+  }).timeout(20000);
+
+  it("should only call a pinned program at its pinned version", async () => {
+    const multisig = await dsl.createMultisig(2, 3);
+    const [ownerA, ownerB, _ownerC] = multisig.owners;
+
+    async function pinInstruction(programDataHash: Array<number>) {
+      return await program.methods
+        .setPinnedPrograms([{programId: MOCK_UPGRADEABLE_PROGRAM, programDataHash: programDataHash}])
+        .accounts({
+          multisig: multisig.address,
+          multisigSigner: multisig.signer,
+        })
+        .instruction();
+    }
+
+    // The mock program rejects this unknown instruction itself, showing the call got past the pin.
+    let callInstruction = new TransactionInstruction({
+      programId: MOCK_UPGRADEABLE_PROGRAM,
+      keys: [{pubkey: dsl.programData(MOCK_UPGRADEABLE_PROGRAM), isWritable: false, isSigner: false}],
+      data: Buffer.alloc(8),
+    });
+
+    await dsl.executeWithQuorum(multisig, [await pinInstruction(await dsl.programDataHash(MOCK_UPGRADEABLE_PROGRAM))]);
+    const currentVersionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [callInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, currentVersionAddress);
+    try {
+      await dsl.executeTransaction(currentVersionAddress, callInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.ok(e.logs.some((log: string) => log.includes("InstructionFallbackNotFound")));
+    }
+
+    // As if the program had been upgraded since the pinned version.
+    await dsl.executeWithQuorum(multisig, [await pinInstruction(Array(32).fill(1))]);
+    const staleVersionAddress: PublicKey = await dsl.proposeTransaction(ownerA, [callInstruction], multisig.address);
+    await dsl.approveTransaction(ownerB, multisig.address, staleVersionAddress);
+    try {
+      await dsl.executeTransaction(staleVersionAddress, callInstruction, multisig.signer, multisig.address, ownerB, ownerA.publicKey);
+      fail("Should have failed to execute transaction");
+    } catch (e) {
+      assert.match(e.message,
+        new RegExp(".*Error Code: ProgramVersionMismatch. Error Number: 6037. Error Message: A called program is not at its pinned version."));
+    }
+  }).timeout(40000);
+});
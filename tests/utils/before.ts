@@ -33,6 +33,10 @@ export interface AnchorConfig {
 
 const PATH_TO_ANCHOR_CONFIG: string = "./Anchor.toml";
 
+// A second copy of the multisig binary, deployed as an upgradeable program whose upgrade authority is the
+// provider wallet, for tests of upgrade authority handoffs.
+export const MOCK_UPGRADEABLE_PROGRAM = new PublicKey("2iuZvpZXQ85Qoiqu6s6RibZBekubNM9yF23FG8q4TwSs");
+
 export const setUpValidator = async (
   deployIdl: Boolean
 ): Promise<{
@@ -52,7 +56,7 @@ export const setUpValidator = async (
     const {signal} = internalController;
 
     exec(
-      `solana-test-validator --ledger ${ledgerDir} --mint ${user.publicKey} --bpf-program ${config.programs.localnet.lmax_multisig} ${config.path.binary_path}`,
+      `solana-test-validator --ledger ${ledgerDir} --mint ${user.publicKey} --bpf-program ${config.programs.localnet.lmax_multisig} ${config.path.binary_path} --upgradeable-program ${MOCK_UPGRADEABLE_PROGRAM} ${config.path.binary_path} ${user.publicKey}`,
       {signal}
     );
 
@@ -1,7 +1,8 @@
-import {Keypair, PublicKey, SystemProgram, Transaction, TransactionInstruction} from "@solana/web3.js";
+import {AccountMeta, BPF_LOADER_UPGRADEABLE_PROGRAM_ID, Keypair, PublicKey, SystemProgram, Transaction, TransactionInstruction} from "@solana/web3.js";
 import {BN, Program, Provider} from "@coral-xyz/anchor";
 import assert from "assert";
-import {Account, createMint, getOrCreateAssociatedTokenAccount, mintToChecked} from "@solana/spl-token";
+import {createHash} from "crypto";
+import {Account, createMint, getOrCreateAssociatedTokenAccount, mintToChecked, TOKEN_PROGRAM_ID} from "@solana/spl-token";
 
 export interface MultisigAccount {
   address: PublicKey;
@@ -11,6 +12,20 @@ export interface MultisigAccount {
   threshold: BN
 }
 
+export interface ProposalOptions {
+  purpose?: number;
+  expiresAt?: BN;
+  requireConsistentSigners?: boolean;
+  designatedExecutor?: PublicKey;
+  payer?: Keypair;
+  memo?: string;
+  displayOrder?: Array<number>;
+  coMultisigs?: Array<PublicKey>;
+  keeperReward?: BN;
+  proposerApproves?: boolean;
+  allowOutOfOrder?: boolean;
+}
+
 export interface TokenMint {
   owner: Keypair;
   account: PublicKey;
@@ -20,20 +35,24 @@ export interface TokenMint {
 export class MultisigDsl {
   readonly program: Program;
   readonly provider: Provider;
+  // Multisigs with an audit log, which is then passed to every lifecycle instruction.
+  readonly auditLogs: Set<string> = new Set();
+  // Multisigs with a stats account, which is then passed to the lifecycle instructions keeping count.
+  readonly statsAccounts: Set<string> = new Set();
 
   constructor(program: Program, provider: Provider) {
     this.program = program;
     this.provider = provider;
   }
 
-  async createMultisigWithOwners(threshold: number, owners: Array<Keypair>, initialBalance: number = 0): Promise<MultisigAccount> {
+  async createMultisigWithOwners(threshold: number, owners: Array<Keypair>, initialBalance: number = 0, minDelay: number = 0, weights?: Array<number>): Promise<MultisigAccount> {
     const multisig = Keypair.generate();
     const [multisigSigner, nonce] = PublicKey.findProgramAddressSync(
       [multisig.publicKey.toBuffer()],
       this.program.programId
     );
     await this.program.methods
-      .createMultisig(owners.map(owner => owner.publicKey), new BN(threshold), nonce)
+      .createMultisig(owners.map(owner => owner.publicKey), new BN(threshold), nonce, new BN(minDelay), weights ? weights.map(weight => new BN(weight)) : null)
       .accounts({
         multisig: multisig.publicKey,
       })
@@ -60,6 +79,68 @@ export class MultisigDsl {
     };
   }
 
+  // A new multisig at the address derived from its creator, who pays for it, and the given seed.
+  async createMultisigWithSeed(threshold: number, owners: Array<Keypair>, seed: Buffer, creator?: Keypair): Promise<MultisigAccount> {
+    const payer = creator ? creator.publicKey : this.provider.publicKey;
+    const [multisig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("multisig"), payer.toBuffer(), seed],
+      this.program.programId
+    );
+    const [multisigSigner, nonce] = PublicKey.findProgramAddressSync(
+      [multisig.toBuffer()],
+      this.program.programId
+    );
+    await this.program.methods
+      .createMultisigWithSeed(owners.map(owner => owner.publicKey), new BN(threshold), nonce, new BN(0), null, Array.from(seed))
+      .accounts({
+        multisig: multisig,
+        payer: payer,
+      })
+      .signers(creator ? [creator] : [])
+      .rpc();
+
+    return {
+      address: multisig,
+      signer: multisigSigner,
+      nonce: nonce,
+      owners: owners,
+      threshold: new BN(threshold)
+    };
+  }
+
+  async exportConfig(multisig: PublicKey) {
+    return await this.program.methods
+      .exportConfig()
+      .accounts({
+        multisig: multisig,
+      })
+      .view();
+  }
+
+  // A new multisig with the owners, threshold and configuration exported from another, whose owner keypairs are given.
+  async createMultisigFromConfig(config: any, owners: Array<Keypair>): Promise<MultisigAccount> {
+    const multisig = Keypair.generate();
+    const [multisigSigner, nonce] = PublicKey.findProgramAddressSync(
+      [multisig.publicKey.toBuffer()],
+      this.program.programId
+    );
+    await this.program.methods
+      .createMultisigFromConfig(config, nonce)
+      .accounts({
+        multisig: multisig.publicKey,
+      })
+      .signers([multisig])
+      .rpc();
+
+    return {
+      address: multisig.publicKey,
+      signer: multisigSigner,
+      nonce: nonce,
+      owners: owners,
+      threshold: config.threshold
+    };
+  }
+
   async createMultisigWithBadNonce(threshold: number, numberOfOwners: number) {
     const owners: Array<Keypair> = Array.from({length: numberOfOwners}, (_, _n) => Keypair.generate());
 
@@ -71,7 +152,7 @@ export class MultisigDsl {
     }
 
     await this.program.methods
-      .createMultisig(owners.map(owner => owner.publicKey), new BN(threshold), nonce + 1)
+      .createMultisig(owners.map(owner => owner.publicKey), new BN(threshold), nonce + 1, new BN(0), null)
       .accounts({
         multisig: multisig.publicKey,
       })
@@ -79,6 +160,155 @@ export class MultisigDsl {
       .rpc();
   }
 
+  // A multisig whose nonce gives a valid, but not the canonical, signer address.
+  async createMultisigWithNonCanonicalNonce(threshold: number, numberOfOwners: number): Promise<MultisigAccount> {
+    const owners: Array<Keypair> = Array.from({length: numberOfOwners}, (_, _n) => Keypair.generate());
+
+    while (true) {
+      const multisig = Keypair.generate();
+      const canonicalNonce = PublicKey.findProgramAddressSync([multisig.publicKey.toBuffer()], this.program.programId)[1];
+      for (let nonce = canonicalNonce - 1; nonce >= 0; nonce--) {
+        let multisigSigner: PublicKey;
+        try {
+          multisigSigner = PublicKey.createProgramAddressSync([multisig.publicKey.toBuffer(), Buffer.from([nonce])], this.program.programId);
+        } catch (e) {
+          continue;  // on the curve, so not a valid signer address
+        }
+        await this.program.methods
+          .createMultisig(owners.map(owner => owner.publicKey), new BN(threshold), nonce, new BN(0), null)
+          .accounts({
+            multisig: multisig.publicKey,
+            multisigSigner: multisigSigner,
+          })
+          .signers([multisig])
+          .rpc();
+        return {
+          address: multisig.publicKey,
+          signer: multisigSigner,
+          nonce: nonce,
+          owners: owners,
+          threshold: new BN(threshold)
+        };
+      }
+    }
+  }
+
+  async createAuditLog(multisig: PublicKey): Promise<PublicKey> {
+    const auditLog = this.auditLogAddress(multisig);
+    await this.program.methods
+      .createAuditLog()
+      .accounts({
+        multisig: multisig,
+        auditLog: auditLog,
+      })
+      .rpc();
+    this.auditLogs.add(multisig.toBase58());
+
+    return auditLog;
+  }
+
+  auditLogAddress(multisig: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("audit_log"), multisig.toBuffer()],
+      this.program.programId
+    )[0];
+  }
+
+  auditLog(multisig: PublicKey): PublicKey | null {
+    return this.auditLogs.has(multisig.toBase58()) ? this.auditLogAddress(multisig) : null;
+  }
+
+  profileAddress(multisig: PublicKey, name: string): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("profile"), multisig.toBuffer(), Buffer.from(name)],
+      this.program.programId
+    )[0];
+  }
+
+  breakGlassAddress(multisig: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("break_glass"), multisig.toBuffer()],
+      this.program.programId
+    )[0];
+  }
+
+  async registerBreakGlass(multisig: MultisigAccount, instructions: Array<TransactionInstruction>, executor: PublicKey, delaySecs: number) {
+    const registerInstruction = await this.program.methods
+      .registerBreakGlass(instructions.map(ix => ({programId: ix.programId, accounts: ix.keys, data: ix.data})), executor, new BN(delaySecs))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+        breakGlass: this.breakGlassAddress(multisig.address),
+        payer: this.provider.publicKey,
+      })
+      .instruction();
+    await this.executeWithQuorum(multisig, [registerInstruction]);
+  }
+
+  async closeBreakGlass(multisig: MultisigAccount) {
+    const closeInstruction = await this.program.methods
+      .closeBreakGlass()
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+        breakGlass: this.breakGlassAddress(multisig.address),
+        refundee: this.provider.publicKey,
+      })
+      .instruction();
+    await this.executeWithQuorum(multisig, [closeInstruction]);
+  }
+
+  async armBreakGlass(executor: Keypair, multisig: MultisigAccount) {
+    await this.program.methods
+      .armBreakGlass()
+      .accounts({
+        multisig: multisig.address,
+        breakGlass: this.breakGlassAddress(multisig.address),
+        executor: executor.publicKey,
+      })
+      .signers([executor])
+      .rpc();
+  }
+
+  async emergencyExecute(executor: Keypair, multisig: MultisigAccount, instructions: Array<TransactionInstruction>) {
+    await this.program.methods
+      .emergencyExecute(instructions.map(ix => ({programId: ix.programId, accounts: ix.keys, data: ix.data})))
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+        breakGlass: this.breakGlassAddress(multisig.address),
+        executor: executor.publicKey,
+      })
+      .remainingAccounts(this.executionAccounts(instructions, [multisig.signer]))
+      .signers([executor])
+      .rpc();
+  }
+
+  async createStats(multisig: PublicKey): Promise<PublicKey> {
+    const stats = this.statsAddress(multisig);
+    await this.program.methods
+      .createStats()
+      .accounts({
+        multisig: multisig,
+        stats: stats,
+      })
+      .rpc();
+    this.statsAccounts.add(multisig.toBase58());
+
+    return stats;
+  }
+
+  statsAddress(multisig: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("stats"), multisig.toBuffer()],
+      this.program.programId
+    )[0];
+  }
+
+  stats(multisig: PublicKey): PublicKey | null {
+    return this.statsAccounts.has(multisig.toBase58()) ? this.statsAddress(multisig) : null;
+  }
+
   async createMultisig(threshold: number, numberOfOwners: number, initialBalance: number = 0): Promise<MultisigAccount> {
     const owners: Array<Keypair> = Array.from({length: numberOfOwners}, (_, _n) => Keypair.generate());
     return await this.createMultisigWithOwners(threshold, owners, initialBalance);
@@ -88,7 +318,8 @@ export class MultisigDsl {
     proposer: Keypair,
     instructions: Array<TransactionInstruction>,
     multisig: PublicKey,
-    transactionAddress?: Keypair
+    transactionAddress?: Keypair,
+    options: ProposalOptions = {}
   ) {
 
     let transactionAccount = transactionAddress ? transactionAddress : Keypair.generate();
@@ -96,11 +327,38 @@ export class MultisigDsl {
       return { programId: ix.programId, accounts: ix.keys, data: ix.data };
     });
     await this.program.methods
-      .createTransaction(smartContractInstructions)
+      .createTransaction(smartContractInstructions, options.purpose ?? 0, options.expiresAt ?? null, options.requireConsistentSigners ?? false, options.designatedExecutor ?? null, options.memo ?? "", options.displayOrder ?? [], options.coMultisigs ?? [], options.keeperReward ?? new BN(0), options.proposerApproves ?? true, options.allowOutOfOrder ?? false)
       .accounts({
           multisig: multisig,
           transaction: transactionAccount.publicKey,
           proposer: proposer.publicKey,
+          auditLog: this.auditLog(multisig),
+          stats: this.stats(multisig),
+          ...(options.payer ? {payer: options.payer.publicKey} : {}),
+      })
+      .signers([proposer, transactionAccount, ...(options.payer ? [options.payer] : [])])
+      .rpc();
+
+    return transactionAccount.publicKey;
+  }
+
+  async proposeUpgradeAuthorityHandoff(
+    proposer: Keypair,
+    multisig: MultisigAccount,
+    program: PublicKey,
+    newAuthority: PublicKey
+  ) {
+    const transactionAccount = Keypair.generate();
+    await this.program.methods
+      .setProgramUpgradeAuthority(newAuthority, "")
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+        transaction: transactionAccount.publicKey,
+        program: program,
+        proposer: proposer.publicKey,
+        auditLog: this.auditLog(multisig.address),
+        stats: this.stats(multisig.address),
       })
       .signers([proposer, transactionAccount])
       .rpc();
@@ -108,10 +366,128 @@ export class MultisigDsl {
     return transactionAccount.publicKey;
   }
 
+  async proposeTokenTransfer(
+    proposer: Keypair,
+    multisig: MultisigAccount,
+    source: PublicKey,
+    destination: PublicKey,
+    amount: number,
+    tokenProgram: PublicKey = TOKEN_PROGRAM_ID
+  ) {
+    const transactionAccount = Keypair.generate();
+    await this.program.methods
+      .proposeTokenTransfer(new BN(amount), "")
+      .accounts({
+        multisig: multisig.address,
+        multisigSigner: multisig.signer,
+        transaction: transactionAccount.publicKey,
+        tokenProgram: tokenProgram,
+        source: source,
+        destination: destination,
+        proposer: proposer.publicKey,
+        auditLog: this.auditLog(multisig.address),
+        stats: this.stats(multisig.address),
+      })
+      .signers([proposer, transactionAccount])
+      .rpc();
+
+    return transactionAccount.publicKey;
+  }
+
+  async markRetain(proposer: Keypair, tx: PublicKey) {
+    await this.program.methods
+      .markRetain()
+      .accounts({
+        transaction: tx,
+        proposer: proposer.publicKey,
+      })
+      .signers([proposer])
+      .rpc();
+  }
+
+  async markAutoExecute(proposer: Keypair, tx: PublicKey) {
+    await this.program.methods
+      .markAutoExecute()
+      .accounts({
+        transaction: tx,
+        proposer: proposer.publicKey,
+      })
+      .signers([proposer])
+      .rpc();
+  }
+
+  // Approves a transaction, passing the accounts needed to execute it should it be auto-executing.
+  async approveAndExecuteTransaction(
+    approver: Keypair,
+    multisig: PublicKey,
+    multisigSigner: PublicKey,
+    tx: PublicKey,
+    ixs: Array<TransactionInstruction>,
+    refundee: PublicKey
+  ) {
+    await this.program.methods
+      .approve()
+      .accounts({
+        multisig: multisig,
+        transaction: tx,
+        owner: approver.publicKey,
+        auditLog: this.auditLog(multisig),
+        multisigSigner,
+        refundee,
+        stats: this.stats(multisig),
+      })
+      .remainingAccounts(this.executionAccounts(ixs, [multisigSigner]))
+      .signers([approver])
+      .rpc();
+  }
+
+  async approveAndExecute(
+    approver: Keypair,
+    multisig: PublicKey,
+    multisigSigner: PublicKey,
+    tx: PublicKey,
+    ixs: Array<TransactionInstruction>,
+    refundee: PublicKey,
+    additionalAccounts: Array<AccountMeta> = []
+  ) {
+    await this.program.methods
+      .approveAndExecute()
+      .accounts({
+        multisig: multisig,
+        transaction: tx,
+        owner: approver.publicKey,
+        auditLog: this.auditLog(multisig),
+        multisigSigner,
+        refundee,
+        stats: this.stats(multisig),
+      })
+      .remainingAccounts(this.executionAccounts(ixs, [multisigSigner]).concat(additionalAccounts))
+      .signers([approver])
+      .rpc();
+  }
+
+  async approveManyTransactions(
+    approver: Keypair,
+    multisig: PublicKey,
+    txs: Array<PublicKey>
+  ) {
+    await this.program.methods
+      .approveMany()
+      .accounts({
+        multisig: multisig,
+        owner: approver.publicKey,
+        auditLog: this.auditLog(multisig),
+      })
+      .remainingAccounts(txs.map(tx => ({pubkey: tx, isSigner: false, isWritable: true})))
+      .signers([approver])
+      .rpc();
+  }
+
   async approveTransaction(
     approver: Keypair,
     multisig: PublicKey,
-    tx: PublicKey
+    tx: PublicKey,
+    additionalAccounts: Array<AccountMeta> = []
   ) {
     await this.program.methods
       .approve()
@@ -119,45 +495,204 @@ export class MultisigDsl {
         multisig: multisig,
         transaction: tx,
         owner: approver.publicKey,
+        auditLog: this.auditLog(multisig),
       })
+      .remainingAccounts(additionalAccounts)
       .signers([approver])
       .rpc();
   }
 
+  async revokeApproval(
+    owner: Keypair,
+    multisig: PublicKey,
+    tx: PublicKey
+  ) {
+    await this.program.methods
+      .revoke()
+      .accounts({
+        multisig: multisig,
+        transaction: tx,
+        owner: owner.publicKey,
+        auditLog: this.auditLog(multisig),
+      })
+      .signers([owner])
+      .rpc();
+  }
+
+  async approvalRoster(multisig: PublicKey, tx: PublicKey): Promise<Array<{owner: PublicKey, approved: boolean}>> {
+    return await this.program.methods
+      .approvalRoster()
+      .accounts({
+        multisig: multisig,
+        transaction: tx,
+      })
+      .view();
+  }
+
+  async approvalReceipt(multisig: PublicKey, tx: PublicKey, owner: PublicKey) {
+    return await this.program.methods
+      .approvalReceipt(owner)
+      .accounts({
+        multisig: multisig,
+        transaction: tx,
+      })
+      .view();
+  }
+
+  async canExecute(multisig: PublicKey, tx: PublicKey, who: PublicKey): Promise<boolean> {
+    return await this.program.methods
+      .canExecute(who)
+      .accounts({
+        multisig: multisig,
+        transaction: tx,
+      })
+      .view();
+  }
+
+  async checkExecutable(multisig: PublicKey, tx: PublicKey): Promise<boolean> {
+    return await this.program.methods
+      .checkExecutable()
+      .accounts({
+        multisig: multisig,
+        transaction: tx,
+      })
+      .view();
+  }
+
+  async effectiveThreshold(multisig: PublicKey, tx: PublicKey): Promise<BN> {
+    return await this.program.methods
+      .effectiveThreshold()
+      .accounts({
+        multisig: multisig,
+        transaction: tx,
+      })
+      .view();
+  }
+
+  async timeUntilExecutable(multisig: PublicKey, tx: PublicKey): Promise<BN> {
+    return await this.program.methods
+      .timeUntilExecutable()
+      .accounts({
+        multisig: multisig,
+        transaction: tx,
+      })
+      .view();
+  }
+
+  // Checks the instructions against the policies of the multisig without proposing them, throwing the first violated.
+  async validateProposal(multisig: PublicKey, instructions: Array<TransactionInstruction>) {
+    const smartContractInstructions = instructions.map(ix => {
+      return { programId: ix.programId, accounts: ix.keys, data: ix.data };
+    });
+    await this.program.methods
+      .validateProposal(smartContractInstructions)
+      .accounts({
+        multisig: multisig,
+      })
+      .rpc();
+  }
+
+  async effectiveExpiry(multisig: PublicKey, tx: PublicKey): Promise<BN> {
+    return await this.program.methods
+      .effectiveExpiry()
+      .accounts({
+        multisig: multisig,
+        transaction: tx,
+      })
+      .view();
+  }
+
   async executeTransactionWithMultipleInstructions(
     tx: PublicKey,
     ixs: Array<TransactionInstruction>,
     multisigSigner: PublicKey,
     multisigAddress: PublicKey,
     executor: Keypair,
+    refundee: PublicKey,
+    purposeSigner?: PublicKey,
+    additionalSigners: Array<Keypair> = [],
+    additionalAccounts: Array<AccountMeta> = []) {
+    const dedupedAccounts = this.executionAccounts(ixs, [multisigSigner, ...(purposeSigner ? [purposeSigner] : [])]).concat(additionalAccounts);
+    await this.program.methods
+      .executeTransaction()
+      .accounts({
+        multisig: multisigAddress,
+        multisigSigner,
+        transaction: tx,
+        executor: executor.publicKey,
+        refundee: refundee,
+        auditLog: this.auditLog(multisigAddress),
+        stats: this.stats(multisigAddress),
+      })
+      .remainingAccounts(dedupedAccounts)
+      .signers([executor, ...additionalSigners])
+      .rpc();
+  }
+
+  async executeWithinLimit(
+    tx: PublicKey,
+    ix: TransactionInstruction,
+    multisigSigner: PublicKey,
+    multisigAddress: PublicKey,
+    executor: Keypair,
     refundee: PublicKey) {
+    await this.program.methods
+      .executeWithinLimit()
+      .accounts({
+        multisig: multisigAddress,
+        multisigSigner,
+        transaction: tx,
+        executor: executor.publicKey,
+        refundee: refundee,
+        auditLog: this.auditLog(multisigAddress),
+        stats: this.stats(multisigAddress),
+      })
+      .remainingAccounts(this.executionAccounts([ix], [multisigSigner]))
+      .signers([executor])
+      .rpc();
+  }
+
+  async executeTransactionByIndex(
+    tx: PublicKey,
+    index: number,
+    ix: TransactionInstruction,
+    multisigSigner: PublicKey,
+    multisigAddress: PublicKey,
+    executor: Keypair,
+    refundee: PublicKey) {
+    await this.program.methods
+      .executeTransactionByIndex(index)
+      .accounts({
+        multisig: multisigAddress,
+        multisigSigner,
+        transaction: tx,
+        executor: executor.publicKey,
+        refundee: refundee,
+        auditLog: this.auditLog(multisigAddress),
+        stats: this.stats(multisigAddress),
+      })
+      .remainingAccounts(this.executionAccounts([ix], [multisigSigner]))
+      .signers([executor])
+      .rpc();
+  }
+
+  // The accounts the given instructions need when invoked by the program, with the multisig's own signers left for it to sign.
+  executionAccounts(ixs: Array<TransactionInstruction>, programSigners: Array<PublicKey>) {
     const accounts = ixs.flatMap(ix =>
       ix.keys
-        .map((meta) => meta.pubkey.equals(multisigSigner)? {...meta, isSigner: false} : meta)
+        .map((meta) => programSigners.some(signer => signer.equals(meta.pubkey)) ? {...meta, isSigner: false} : meta)
         .concat({
           pubkey: ix.programId,
           isWritable: false,
           isSigner: false,
         })
     );
-    const dedupedAccounts = accounts.filter((value, index) => {
+    return accounts.filter((value, index) => {
       const _value = JSON.stringify(value);
       return index === accounts.findIndex(obj => {
         return JSON.stringify(obj) === _value;
       });
     });
-    await this.program.methods
-      .executeTransaction()
-      .accounts({
-        multisig: multisigAddress,
-        multisigSigner,
-        transaction: tx,
-        executor: executor.publicKey,
-        refundee: refundee
-      })
-      .remainingAccounts(dedupedAccounts)
-      .signers([executor])
-      .rpc();
   }
 
   async executeTransaction(
@@ -170,6 +705,21 @@ export class MultisigDsl {
     await this.executeTransactionWithMultipleInstructions(tx, [ix], multisigSigner, multisigAddress, executor, refundee);
   }
 
+  async approveCancel(
+    tx: PublicKey,
+    multisigAddress: PublicKey,
+    owner: Keypair) {
+    await this.program.methods
+      .approveCancel()
+      .accounts({
+        multisig: multisigAddress,
+        transaction: tx,
+        owner: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc();
+  }
+
   async cancelTransaction(
     tx: PublicKey,
     multisigAddress: PublicKey,
@@ -181,12 +731,65 @@ export class MultisigDsl {
         multisig: multisigAddress,
         transaction: tx,
         executor: executor.publicKey,
-        refundee: refundee
+        refundee: refundee,
+        auditLog: this.auditLog(multisigAddress),
+        stats: this.stats(multisigAddress),
       })
       .signers([executor])
       .rpc();
   }
 
+  async deleteTransaction(
+    tx: PublicKey,
+    multisigAddress: PublicKey,
+    proposer: Keypair,
+    refundee: PublicKey) {
+    await this.program.methods
+      .deleteTransaction()
+      .accounts({
+        multisig: multisigAddress,
+        transaction: tx,
+        refundee: refundee,
+        proposer: proposer.publicKey,
+        auditLog: this.auditLog(multisigAddress),
+        stats: this.stats(multisigAddress),
+      })
+      .signers([proposer])
+      .rpc();
+  }
+
+  async freeze(multisigAddress: PublicKey, owner: Keypair) {
+    await this.program.methods
+      .freeze()
+      .accounts({
+        multisig: multisigAddress,
+        owner: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc();
+  }
+
+  async acceptOwnership(multisigAddress: PublicKey, pendingOwner: Keypair) {
+    await this.program.methods
+      .acceptOwnership()
+      .accounts({
+        multisig: multisigAddress,
+        pendingOwner: pendingOwner.publicKey,
+      })
+      .signers([pendingOwner])
+      .rpc();
+  }
+
+  // Proposes, approves with just enough owners and executes the given instructions, e.g. to govern the multisig itself.
+  async executeWithQuorum(multisig: MultisigAccount, instructions: Array<TransactionInstruction>) {
+    const [proposer, ...approvers] = multisig.owners.slice(0, multisig.threshold.toNumber());
+    const transactionAddress = await this.proposeTransaction(proposer, instructions, multisig.address);
+    for (const approver of approvers) {
+      await this.approveTransaction(approver, multisig.address, transactionAddress);
+    }
+    await this.executeTransactionWithMultipleInstructions(transactionAddress, instructions, multisig.signer, multisig.address, proposer, proposer.publicKey);
+  }
+
   async proposeSignAndExecuteTransaction(
     proposer: Keypair,
     signers: Array<Keypair>,
@@ -202,11 +805,13 @@ export class MultisigDsl {
       return { programId: ix.programId, accounts: ix.keys, data: ix.data };
     });
     const proposeInstruction = await this.program.methods
-      .createTransaction(smartContractInstructions)
+      .createTransaction(smartContractInstructions, 0, null, false, null, "", [], [], new BN(0), true, false)
       .accounts({
         multisig: multisigAddress,
         transaction: transactionAccount.publicKey,
         proposer: proposer.publicKey,
+        auditLog: this.auditLog(multisigAddress),
+        stats: this.stats(multisigAddress),
       })
       .signers([proposer, transactionAccount])
       .instruction();
@@ -218,6 +823,7 @@ export class MultisigDsl {
         multisig: multisigAddress,
         transaction: transactionAccount.publicKey,
         owner: signer.publicKey,
+        auditLog: this.auditLog(multisigAddress),
       })
       .signers([signer])
       .instruction()
@@ -245,7 +851,9 @@ export class MultisigDsl {
         multisigSigner,
         transaction: transactionAccount.publicKey,
         executor: executor.publicKey,
-        refundee: refundee
+        refundee: refundee,
+        auditLog: this.auditLog(multisigAddress),
+        stats: this.stats(multisigAddress),
       })
       .remainingAccounts(dedupedAccounts)
       .signers([executor])
@@ -261,6 +869,47 @@ export class MultisigDsl {
     await this.provider.sendAndConfirm(transaction);
   }
 
+  ownersHash(owners: Array<PublicKey>): Array<number> {
+    return Array.from(createHash("sha256").update(Buffer.concat(owners.map(owner => owner.toBuffer()))).digest());
+  }
+
+  purposeSigner(multisig: PublicKey, purpose: number): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [multisig.toBuffer(), Buffer.from([purpose])],
+      this.program.programId
+    )[0];
+  }
+
+  programData(program: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync([program.toBuffer()], BPF_LOADER_UPGRADEABLE_PROGRAM_ID)[0];
+  }
+
+  // SHA-256 of the deployed code of an upgradeable program, i.e. its program data after the metadata.
+  async programDataHash(program: PublicKey): Promise<Array<number>> {
+    const programData = await this.provider.connection.getAccountInfo(this.programData(program), "confirmed");
+    return Array.from(createHash("sha256").update(programData.data.subarray(45)).digest());
+  }
+
+  setUpgradeAuthorityInstruction(program: PublicKey, currentAuthority: PublicKey, newAuthority: PublicKey): TransactionInstruction {
+    const data = Buffer.alloc(4);
+    data.writeUInt32LE(4);
+    return new TransactionInstruction({
+      programId: BPF_LOADER_UPGRADEABLE_PROGRAM_ID,
+      keys: [
+        {pubkey: this.programData(program), isWritable: true, isSigner: false},
+        {pubkey: currentAuthority, isWritable: false, isSigner: true},
+        {pubkey: newAuthority, isWritable: false, isSigner: false},
+      ],
+      data: data,
+    });
+  }
+
+  async assertUpgradeAuthority(program: PublicKey, expectedAuthority: PublicKey) {
+    const programData = await this.provider.connection.getAccountInfo(this.programData(program), "confirmed");
+    assert.strictEqual(programData.data[12], 1);
+    assert.ok(new PublicKey(programData.data.subarray(13, 45)).equals(expectedAuthority));
+  }
+
   async assertBalance(address: PublicKey, expectedBalance: number) {
     let actualBalance = await this.provider.connection.getBalance(address, "confirmed");
     assert.strictEqual(actualBalance, expectedBalance);